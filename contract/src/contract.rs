@@ -4,16 +4,16 @@
 
 mod state;
 
-use linera_roulette::{Operation, RouletteAbi, Player, Bet, SpinResult, Winner, GameState, get_color, Message, InstantiationArgument};
+use linera_roulette::{Operation, RouletteAbi, Player, Bet, SpinResult, GameState, PlayerPayout, settle_bets, get_color, Message, InstantiationArgument, RoundAction, TableConfig, TableId, TableInfo, HostListing, PendingJoin, LeavePolicy, RouletteEvent, TableMode, Room, RoomAction, PlayerV1, SCHEMA_VERSION, DEFAULT_TABLE_ID, EVENT_STREAM_NAME, BetError, validate_bet, PendingRequest, RequestError, RequestId, RequestKind, push_request_error, BounceAction, bounce_action, NonceError, check_nonce, Autoplay, AutoplayStop, BetType, BetLayout, strategy::{StrategyRun, StrategyStop}, limits::{LimitChange, LimitError, ResponsibleGaming}};
 use linera_sdk::{
-    linera_base_types::{WithContractAbi, ChainId},
-    views::{RootView, View},
+    linera_base_types::{WithContractAbi, ChainId, StreamUpdate},
+    views::{RootView, View, ViewStorageContext},
     Contract, ContractRuntime,
 };
 use sha2::{Sha256, Digest};
 use std::str::FromStr;
-use std::collections::HashMap;
-use self::state::RouletteState;
+use std::collections::{BTreeMap, HashMap};
+use self::state::{RouletteState, RouletteStateV1};
 
 linera_sdk::contract!(RouletteContract);
 
@@ -33,22 +33,51 @@ impl Contract for RouletteContract {
     type EventValue = linera_roulette::RouletteEvent;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
-        let state = RouletteState::load(runtime.root_view_storage_context())
-            .await
-            .expect("Failed to load state");
-        RouletteContract { state, runtime }
+        let context = runtime.root_view_storage_context();
+        // A version 1 chain is converted wholesale.
+        let (state, upgraded) = match RouletteState::load_current(context.clone()).await {
+            Ok(state) => (state, false),
+            Err(legacy) => (Self::upgrade_from_v1(legacy, context).await, true),
+        };
+        let mut contract = RouletteContract { state, runtime };
+        if upgraded {
+            contract.follow_upgraded_host();
+        }
+        contract.migrate();
+        contract
     }
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
+        self.state.schema_version.set(SCHEMA_VERSION);
+        if argument.mode == TableMode::Solo {
+            self.state.is_host.set(true);
+            self.state.is_solo.set(true);
+            self.create_table(argument.table_config);
+            return;
+        }
+        self.state.registry_chain_id.set(argument.registry_chain_id);
+        if argument.registry {
+            self.state.is_registry.set(true);
+            return;
+        }
+        // A spectator never hosts; without a host it waits for `Operation::Spectate`.
+        if argument.spectator {
+            self.state.is_spectator.set(true);
+            if let Some(host_id) = argument.host_chain_id {
+                self.bind_host(host_id);
+            }
+            return;
+        }
         match argument.host_chain_id {
             Some(host_id) => {
                 self.state.is_host.set(false);
-                self.state.host_chain_id.set(Some(host_id));
+                self.bind_host(host_id);
             }
             None => {
                 self.state.is_host.set(true);
                 self.state.host_chain_id.set(None);
-                self.state.game.set(GameState::new());
+                self.create_table(argument.table_config);
+                self.announce_tables().await;
             }
         }
     }
@@ -57,29 +86,93 @@ impl Contract for RouletteContract {
         let is_host = *self.state.is_host.get();
         let chain_id = self.runtime.chain_id().to_string();
 
+        if *self.state.is_registry.get() {
+            self.execute_room_operation(operation).await;
+            return;
+        }
+
+        // Any other chain leases rooms for itself, through the registry.
+        let operation = match operation {
+            Operation::EnableRegistry => return self.enable_registry(),
+            Operation::ClaimRoom { registry_chain_id, chain_id } => return self.request_room(&registry_chain_id, chain_id, RoomAction::Claim),
+            Operation::ReleaseRoom { registry_chain_id, chain_id } => return self.request_room(&registry_chain_id, chain_id, RoomAction::Release),
+            Operation::HeartbeatRoom { registry_chain_id, chain_id } => return self.request_room(&registry_chain_id, chain_id, RoomAction::Heartbeat),
+            operation => operation,
+        };
+
+        // Spectators only ever choose which host to watch.
+        if *self.state.is_spectator.get() {
+            if let Operation::Spectate { host_chain_id } = operation {
+                self.bind_host(host_chain_id);
+            }
+            return;
+        }
+
         if !is_host {
+            self.expire_requests().await;
+            if let Operation::JoinTable { host_chain_id, table_id, invite_code } = operation {
+                self.join_table(host_chain_id, table_id, invite_code).await;
+                return;
+            }
+            // Only a chain without a seat can turn into a spectator.
+            if let Operation::Spectate { host_chain_id } = operation {
+                if self.state.my_player.get().is_none() {
+                    self.state.is_spectator.set(true);
+                    self.bind_host(host_chain_id);
+                }
+                return;
+            }
             if let Some(host_chain_id_str) = self.state.host_chain_id.get().clone() {
                 if let Ok(host_chain) = ChainId::from_str(&host_chain_id_str) {
                     match operation {
-                        Operation::RegisterPlayer { name, initial_balance, .. } => {
+                        Operation::RegisterPlayer { table_id, name, initial_balance, invite_code, .. } => {
+                            let request_id = self.track_request(RequestKind::Register, table_id, initial_balance);
                             self.runtime
-                                .prepare_message(Message::RegisterPlayerRequest { name, initial_balance })
+                                .prepare_message(Message::RegisterPlayerRequest { request_id, table_id, name, initial_balance, invite_code })
                                 .with_tracking()
                                 .send_to(host_chain);
                         }
-                        Operation::PlaceBet { bet_type, numbers, amount, .. } => {
-                            let player_name = self.state.my_player.get()
-                                .as_ref()
-                                .map(|p| p.name.clone())
-                                .unwrap_or_default();
+                        Operation::PlaceBet { table_id, bet_type, numbers, amount, .. } => {
+                            self.send_bet(host_chain, table_id, bet_type, numbers, amount);
+                        }
+                        Operation::SpinWheel { table_id } => {
+                            let request_id = self.next_request_id();
                             self.runtime
-                                .prepare_message(Message::PlaceBetRequest { bet_type, numbers, amount, player_name })
+                                .prepare_message(Message::SpinWheelRequest { request_id, table_id, player_chain_id: chain_id })
                                 .with_tracking()
                                 .send_to(host_chain);
                         }
-                        Operation::SpinWheel => {
+                        Operation::LeaveTable => {
+                            let table_id = self.state.my_player.get().as_ref().map_or(DEFAULT_TABLE_ID, |p| p.table_id);
+                            let request_id = self.track_request(RequestKind::Leave, table_id, 0);
                             self.runtime
-                                .prepare_message(Message::SpinWheelRequest { player_chain_id: chain_id })
+                                .prepare_message(Message::LeaveTableRequest { request_id })
+                                .with_tracking()
+                                .send_to(host_chain);
+                        }
+                        Operation::SyncBalance => self.request_balance_snapshot(host_chain),
+                        Operation::StartAutoplay { bets, rounds, stop_loss, take_profit } => {
+                            if self.is_betting_automatically() { return; }
+                            let Some(player) = self.state.my_player.get().clone() else { return };
+                            let Ok(autoplay) = Autoplay::new(player.table_id, bets, rounds, stop_loss, take_profit, player.balance) else { return };
+                            self.state.autoplay.set(Some(autoplay));
+                            self.play_autoplay_round(player.balance);
+                        }
+                        Operation::StopAutoplay => self.stop_autoplay(AutoplayStop::Stopped),
+                        Operation::StartStrategy { kind, bet } => {
+                            if self.is_betting_automatically() || bet.amount == 0 { return; }
+                            let Some(player) = self.state.my_player.get().clone() else { return };
+                            let mut run = StrategyRun::new(player.table_id, kind, bet);
+                            let bets = run.next_round(None, player.balance);
+                            self.state.strategy.set(Some(run));
+                            self.send_bets(host_chain, player.table_id, bets);
+                        }
+                        Operation::StopStrategy => self.stop_strategy(StrategyStop::Stopped),
+                        Operation::ChangeLimits { change } => {
+                            let table_id = self.state.my_player.get().as_ref().map_or(DEFAULT_TABLE_ID, |p| p.table_id);
+                            let request_id = self.track_request(RequestKind::Limits, table_id, 0);
+                            self.runtime
+                                .prepare_message(Message::LimitsRequest { request_id, change })
                                 .with_tracking()
                                 .send_to(host_chain);
                         }
@@ -90,227 +183,1108 @@ impl Contract for RouletteContract {
             return;
         }
 
+        if *self.state.is_solo.get() {
+            self.execute_solo_operation(operation).await;
+            return;
+        }
+
+        self.advance_rounds().await;
+        self.retry_deliveries().await;
+
         match operation {
-            Operation::RegisterPlayer { player_id, name, initial_balance } => {
+            // The host registers players directly, so table access rules do not apply.
+            Operation::RegisterPlayer { table_id, player_id, name, initial_balance, .. } => {
+                if self.table_config(table_id).await.is_none() { return; }
                 if let Ok(Some(_)) = self.state.players.get(&player_id).await {
                     return;
                 }
-                let player = Player { chain_id: player_id.clone(), name: name.clone(), balance: initial_balance };
+                let player = Player { chain_id: player_id.clone(), name: name.clone(), balance: initial_balance, table_id };
                 let _ = self.state.players.insert(&player_id, player.clone());
                 let _ = self.state.player_chains.insert(&player_id);
                 let timestamp = self.runtime.system_time().micros().to_string();
-                self.runtime.emit("roulette_events".into(), &linera_roulette::RouletteEvent::PlayerRegistered { player, timestamp });
+                self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::PlayerRegistered { player, timestamp });
+                self.announce_tables().await;
             }
 
-            Operation::PlaceBet { player_id, bet_type, numbers, amount } => {
-                let mut player = match self.state.players.get(&player_id).await {
-                    Ok(Some(p)) => p,
+            Operation::PlaceBet { table_id, player_id, bet_type, numbers, amount } => {
+                let player_name = match self.state.players.get(&player_id).await {
+                    Ok(Some(p)) => p.name,
                     _ => return,
                 };
-                if player.balance < amount { return; }
-                let mut game = self.state.game.get().clone();
-                if game.is_spinning { return; }
+                let bet = Bet { table_id, player_chain_id: player_id, player_name, bet_type, numbers, amount };
+                let _ = self.place_bet(&bet).await;
+            }
 
-                player.balance -= amount;
-                let _ = self.state.players.insert(&player_id, player.clone());
+            Operation::StartRound { table_id } => self.start_round(table_id).await,
 
-                let bet = Bet {
-                    player_chain_id: player_id,
-                    player_name: player.name.clone(),
-                    bet_type,
-                    numbers,
-                    amount,
-                };
-                game.current_bets.push(bet.clone());
-                self.state.game.set(game);
+            Operation::SpinWheel { table_id } => self.spin_wheel(table_id).await,
 
+            Operation::SettleBets { table_id, result } => {
+                if self.game(table_id).await.is_none() { return; }
                 let timestamp = self.runtime.system_time().micros().to_string();
-                self.runtime.emit("roulette_events".into(), &linera_roulette::RouletteEvent::BetPlaced { bet, timestamp });
+                self.settle_bets_internal(table_id, result, timestamp).await;
             }
 
-            Operation::StartRound => {
-                let now_ms = self.runtime.system_time().micros() / 1000;
-                let mut game = self.state.game.get().clone();
-                game.betting_end_time = Some(now_ms + 30_000);
-                self.state.game.set(game);
+            Operation::CreateTable { config } => {
+                self.create_table(config);
+                self.announce_tables().await;
             }
 
-            Operation::SpinWheel => {
-                let mut game = self.state.game.get().clone();
-                if game.is_spinning || game.current_bets.is_empty() { return; }
-                game.is_spinning = true;
-                self.state.game.set(game.clone());
-                let result = self.generate_random_number(&game);
-                let timestamp = self.runtime.system_time().micros().to_string();
-                self.settle_bets_internal(result, timestamp).await;
+            Operation::ConfigureTable { table_id, config } => {
+                if self.table_config(table_id).await.is_some() {
+                    self.update_table_config(table_id, config).await;
+                }
             }
 
-            Operation::SettleBets { result } => {
-                let timestamp = self.runtime.system_time().micros().to_string();
-                self.settle_bets_internal(result, timestamp).await;
+            // Round advancement already ran above; a tick only needs to trigger it.
+            Operation::Tick => {}
+
+            Operation::AnnounceTables => self.announce_tables().await,
+
+            // Only player chains switch tables.
+            Operation::JoinTable { .. } => {}
+
+            Operation::AddTableMember { table_id, chain_id } => {
+                if let Some(mut config) = self.table_config(table_id).await {
+                    config.access.add_member(chain_id);
+                    self.update_table_config(table_id, config).await;
+                }
             }
+
+            Operation::RemoveTableMember { table_id, chain_id } => {
+                if let Some(mut config) = self.table_config(table_id).await {
+                    config.access.remove_member(&chain_id);
+                    self.update_table_config(table_id, config).await;
+                }
+            }
+
+            // Only player chains leave tables; the host removes players with `KickPlayer`.
+            Operation::LeaveTable => {}
+
+            Operation::KickPlayer { chain_id } => { self.remove_player(&chain_id, true, None).await; }
+
+            Operation::Spectate { .. } => {}
+
+            // The host's balances are authoritative already.
+            Operation::SyncBalance => {}
+
+            // Rooms are handed out by registry chains.
+            Operation::AddRoom { .. }
+            | Operation::ClaimRoom { .. }
+            | Operation::ReleaseRoom { .. }
+            | Operation::HeartbeatRoom { .. }
+            | Operation::EnableRegistry => {}
+
+            Operation::ResetTable { table_id, notify_players } => self.reset_table(table_id, notify_players).await,
+
+            // Autoplay and strategies run on player chains.
+            Operation::StartAutoplay { .. }
+            | Operation::StopAutoplay
+            | Operation::StartStrategy { .. }
+            | Operation::StopStrategy => {}
+
+            // Players change their own limits, from their own chains.
+            Operation::ChangeLimits { .. } => {}
         }
     }
 
     async fn execute_message(&mut self, message: Self::Message) {
         let sender_chain = self.runtime.message_origin_chain_id().expect("Missing origin chain");
 
+        if *self.state.is_spectator.get() { return; }
+
+        if *self.state.is_host.get() {
+            self.advance_rounds().await;
+        } else {
+            self.expire_requests().await;
+        }
+
+        if self.runtime.message_is_bouncing() == Some(true) {
+            self.handle_bounce(sender_chain, message).await;
+            return;
+        }
+
+        // The sender is reachable again, so its bounce count starts over.
+        if let Ok(Some(queue)) = self.state.undelivered.get(&sender_chain.to_string()).await {
+            if queue.messages.is_empty() {
+                let _ = self.state.undelivered.remove(&sender_chain.to_string());
+            }
+        }
+
+        // Anyone can message a player chain; only its own host may speak for the table.
+        if message.is_host_reply() && self.host_chain() != Some(sender_chain) { return; }
+
+        if let Some(request_id) = message.request_id() {
+            if let Err(error) = self.check_request_nonce(sender_chain, request_id).await {
+                self.reject_replay(sender_chain, message, &error);
+                return;
+            }
+        }
+
         match message {
-            Message::RegisterPlayerRequest { name, initial_balance } => {
+            Message::RegisterPlayerRequest { request_id, table_id, name, initial_balance, invite_code } => {
                 let chain_id = sender_chain.to_string();
-                if let Ok(Some(_)) = self.state.players.get(&chain_id).await {
+                let host_chain_id = self.runtime.chain_id().to_string();
+                let error_message = match self.table_config(table_id).await {
+                    None => Some("Unknown table".to_string()),
+                    Some(_) if matches!(self.state.players.get(&chain_id).await, Ok(Some(_))) => {
+                        Some("Already registered".to_string())
+                    }
+                    Some(config) => config.access.admits(&chain_id, invite_code.as_deref(), &host_chain_id, table_id).err(),
+                };
+                if error_message.is_some() {
                     self.runtime
                         .prepare_message(Message::PlayerRegisteredConfirm {
-                            player: Player { chain_id, name, balance: 0 },
+                            request_id,
+                            player: Player { chain_id, name, balance: 0, table_id },
                             success: false,
-                            error_message: Some("Already registered".to_string()),
+                            error_message,
                         })
                         .with_tracking()
                         .send_to(sender_chain);
                     return;
                 }
 
-                let player = Player { chain_id: chain_id.clone(), name, balance: initial_balance };
+                let player = Player { chain_id: chain_id.clone(), name, balance: initial_balance, table_id };
                 let _ = self.state.players.insert(&chain_id, player.clone());
                 let _ = self.state.player_chains.insert(&chain_id);
 
                 let timestamp = self.runtime.system_time().micros().to_string();
-                self.runtime.emit("roulette_events".into(), &linera_roulette::RouletteEvent::PlayerRegistered {
+                self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::PlayerRegistered {
                     player: player.clone(),
                     timestamp,
                 });
 
                 self.runtime
-                    .prepare_message(Message::PlayerRegisteredConfirm { player, success: true, error_message: None })
+                    .prepare_message(Message::PlayerRegisteredConfirm { request_id, player, success: true, error_message: None })
                     .with_tracking()
                     .send_to(sender_chain);
+                self.announce_tables().await;
             }
 
-            Message::PlaceBetRequest { bet_type, numbers, amount, player_name } => {
-                let chain_id = sender_chain.to_string();
-                let mut player = match self.state.players.get(&chain_id).await {
-                    Ok(Some(p)) => p,
-                    _ => {
+            Message::PlaceBetRequest { request_id, table_id, bet_type, numbers, amount, player_name } => {
+                let bet = Bet { table_id, player_chain_id: sender_chain.to_string(), player_name, bet_type, numbers, amount };
+                match self.place_bet(&bet).await {
+                    Ok(new_balance) => {
                         self.runtime
-                            .prepare_message(Message::BetPlacedConfirm {
-                                bet: Bet { player_chain_id: chain_id, player_name, bet_type, numbers, amount },
-                                success: false,
-                                new_balance: 0,
-                                error_message: Some("Not registered".to_string()),
-                            })
+                            .prepare_message(Message::BetPlacedConfirm { request_id, bet, success: true, new_balance, error_message: None })
                             .with_tracking()
                             .send_to(sender_chain);
-                        return;
                     }
+                    Err(error) => {
+                        let balance = self.player_balance(&bet.player_chain_id).await;
+                        self.reject_bet(sender_chain, request_id, bet, balance, error.to_string());
+                    }
+                }
+            }
+
+            Message::PlayerRegisteredConfirm { request_id, player, success, error_message } => {
+                // Answers to requests we gave up on, or never sent, are not acted on.
+                let error_message = (!success).then(|| error_message.unwrap_or_default());
+                if self.resolve_request(request_id, error_message).await.is_none() { return; }
+                if success {
+                    let chain_id = player.chain_id.clone();
+                    let _ = self.state.players.insert(&chain_id, player.clone());
+                    self.state.my_player.set(Some(player));
+                    self.state.wallet_balance.set(0);
+                    self.state.balance_is_stale.set(false);
+                }
+            }
+
+            Message::BetPlacedConfirm { request_id, success, new_balance, error_message, .. } => {
+                let error_message = (!success).then(|| error_message.unwrap_or_default());
+                if self.resolve_request(request_id, error_message).await.is_none() { return; }
+                if success {
+                    self.set_my_balance(new_balance).await;
+                } else {
+                    self.stop_autoplay(AutoplayStop::BetRejected);
+                    self.stop_strategy(StrategyStop::BetRejected);
+                }
+            }
+
+            Message::SpinResultBroadcast { table_id, number, new_balance, .. } => {
+                self.set_my_balance(new_balance).await;
+                self.state.balance_is_stale.set(false);
+                let next_round = self.state.autoplay.get_mut()
+                    .as_mut()
+                    .is_some_and(|autoplay| autoplay.table_id == table_id && autoplay.round_settled(new_balance));
+                if next_round {
+                    self.play_autoplay_round(new_balance);
+                }
+                // The broadcast carries only the pocket, which is all a strategy reads.
+                let timestamp = self.runtime.system_time().micros().to_string();
+                let result = SpinResult { number, color: get_color(number), timestamp, winners: Vec::new() };
+                let bets = match self.state.strategy.get_mut() {
+                    Some(run) if run.table_id == table_id => run.next_round(Some(&result), new_balance),
+                    _ => Vec::new(),
                 };
+                if let Some(host_chain) = self.host_chain() {
+                    self.send_bets(host_chain, table_id, bets);
+                }
+            }
+
+            Message::BalanceUpdate { new_balance, .. } => self.set_my_balance(new_balance).await,
 
-                if player.balance < amount {
+            Message::SpinWheelRequest { table_id, .. } => {
+                if !*self.state.is_host.get() { return; }
+                self.spin_wheel(table_id).await;
+            }
+
+            Message::LeaveTableRequest { request_id } => {
+                if !*self.state.is_host.get() { return; }
+                let chain_id = sender_chain.to_string();
+                if !self.remove_player(&chain_id, false, Some(request_id)).await {
                     self.runtime
-                        .prepare_message(Message::BetPlacedConfirm {
-                            bet: Bet { player_chain_id: chain_id, player_name, bet_type, numbers, amount },
+                        .prepare_message(Message::LeaveTableConfirm {
+                            request_id: Some(request_id),
+                            player: Player { chain_id, name: String::new(), balance: 0, table_id: 0 },
                             success: false,
-                            new_balance: player.balance,
-                            error_message: Some("Insufficient balance".to_string()),
+                            kicked: false,
+                            error_message: Some("Not registered".to_string()),
                         })
                         .with_tracking()
                         .send_to(sender_chain);
-                    return;
                 }
+            }
 
-                let mut game = self.state.game.get().clone();
-                if game.is_spinning {
-                    self.runtime
-                        .prepare_message(Message::BetPlacedConfirm {
-                            bet: Bet { player_chain_id: chain_id, player_name, bet_type, numbers, amount },
-                            success: false,
-                            new_balance: player.balance,
-                            error_message: Some("Wheel spinning".to_string()),
-                        })
-                        .with_tracking()
-                        .send_to(sender_chain);
-                    return;
+            Message::LeaveTableConfirm { request_id, player, success, error_message, .. } => {
+                if let Some(request_id) = request_id {
+                    let error_message = (!success).then(|| error_message.unwrap_or_default());
+                    if self.resolve_request(request_id, error_message).await.is_none() { return; }
                 }
+                // A confirm for a seat at another table is stale; the player has moved on.
+                if self.state.my_player.get().as_ref().is_some_and(|seat| seat.table_id != player.table_id) { return; }
+                let pending_join = self.state.pending_join.get().clone();
+                self.state.pending_join.set(None);
+                if !success { return; }
 
-                player.balance -= amount;
-                let new_balance = player.balance;
-                let _ = self.state.players.insert(&chain_id, player);
+                self.unseat();
+                self.state.wallet_balance.set(player.balance);
 
-                let bet = Bet { player_chain_id: chain_id.clone(), player_name, bet_type, numbers, amount };
-                game.current_bets.push(bet.clone());
-                self.state.game.set(game);
+                // The old host released the chips; carry the balance over to the new table.
+                if let Some(join) = pending_join {
+                    if let Ok(host_chain) = ChainId::from_str(&join.host_chain_id) {
+                        self.bind_host(join.host_chain_id);
+                        self.request_seat(host_chain, join.table_id, player.name, player.balance, join.invite_code);
+                    }
+                }
+            }
 
-                let timestamp = self.runtime.system_time().micros().to_string();
-                self.runtime.emit("roulette_events".into(), &linera_roulette::RouletteEvent::BetPlaced {
-                    bet: bet.clone(),
-                    timestamp,
-                });
+            Message::RoomRequest { chain_id, action } => {
+                if !*self.state.is_registry.get() { return; }
+                self.update_room(sender_chain, chain_id, action).await;
+            }
+
+            Message::AnnounceTables { tables } => {
+                if !*self.state.is_registry.get() { return; }
+                let host_chain_id = sender_chain.to_string();
+                let listing = HostListing { host_chain_id: host_chain_id.clone(), tables, updated_at: self.now_ms() };
+                let _ = self.state.listings.insert(&host_chain_id, listing);
+            }
 
+            Message::LimitsRequest { request_id, change } => {
+                if !*self.state.is_host.get() { return; }
+                let (settings, result) = self.change_limits(&sender_chain.to_string(), change).await;
                 self.runtime
-                    .prepare_message(Message::BetPlacedConfirm { bet, success: true, new_balance, error_message: None })
+                    .prepare_message(Message::LimitsConfirm { request_id, settings, error_message: result.err().map(|e| e.to_string()) })
                     .with_tracking()
                     .send_to(sender_chain);
             }
 
-            Message::PlayerRegisteredConfirm { player, success, .. } => {
-                if success {
-                    let chain_id = player.chain_id.clone();
-                    let _ = self.state.players.insert(&chain_id, player.clone());
-                    self.state.my_player.set(Some(player));
-                }
+            Message::LimitsConfirm { request_id, settings, error_message } => {
+                if self.resolve_request(request_id, error_message).await.is_none() { return; }
+                let chain_id = self.runtime.chain_id().to_string();
+                let _ = self.state.responsible_gaming.insert(&chain_id, settings);
             }
 
-            Message::BetPlacedConfirm { bet, success, new_balance, .. } => {
-                if success {
-                    if let Ok(Some(mut player)) = self.state.players.get(&bet.player_chain_id).await {
-                        player.balance = new_balance;
-                        let _ = self.state.players.insert(&bet.player_chain_id, player);
+            Message::BalanceSnapshotRequest { request_id } => {
+                if !*self.state.is_host.get() { return; }
+                let player = self.state.players.get(&sender_chain.to_string()).await.ok().flatten();
+                self.runtime
+                    .prepare_message(Message::BalanceSnapshot { request_id, player })
+                    .with_tracking()
+                    .send_to(sender_chain);
+            }
+
+            Message::BalanceSnapshot { player, .. } => {
+                match player {
+                    Some(player) => {
+                        let chain_id = player.chain_id.clone();
+                        let _ = self.state.players.insert(&chain_id, player.clone());
+                        self.state.my_player.set(Some(player));
                     }
+                    // The host no longer seats us; the chips already went back to the wallet.
+                    None => self.unseat(),
                 }
+                self.state.balance_is_stale.set(false);
             }
+        }
+    }
 
-            Message::SpinResultBroadcast { result, new_balance, .. } => {
-                let mut game = self.state.game.get().clone();
-                game.last_result = Some(result);
-                game.is_spinning = false;
-                self.state.game.set(game);
+    async fn process_streams(&mut self, updates: Vec<StreamUpdate>) {
+        let Some(host_chain) = self.host_chain() else { return };
+        let stream_name = EVENT_STREAM_NAME.into();
+        for update in updates {
+            if update.chain_id != host_chain || update.stream_id.stream_name != stream_name { continue; }
+            for index in update.new_indices() {
+                let event = self.runtime.read_event(update.chain_id, EVENT_STREAM_NAME.into(), index);
+                // Reconcile after each round we had chips in, in case a payout message went astray.
+                let seated_at = self.state.my_player.get().as_ref().map(|p| p.table_id);
+                let staked = match &event {
+                    RouletteEvent::WheelSpun { table_id, .. } if Some(*table_id) == seated_at => self.has_open_bets(*table_id).await,
+                    _ => false,
+                };
+                let reset_table = match &event {
+                    RouletteEvent::TableReset { table_id, .. } => Some(*table_id),
+                    _ => None,
+                };
+                self.mirror_event(event).await;
+                // A reset unseats everyone; any payout arrives separately as a `LeaveTableConfirm`.
+                if reset_table.is_some() && reset_table == self.state.my_player.get().as_ref().map(|p| p.table_id) {
+                    self.unseat();
+                }
+                if staked {
+                    self.state.balance_is_stale.set(true);
+                    self.request_balance_snapshot(host_chain);
+                }
+            }
+        }
+    }
 
-                let chain_id = self.runtime.chain_id().to_string();
-                if let Ok(Some(mut player)) = self.state.players.get(&chain_id).await {
-                    player.balance = new_balance;
-                    let _ = self.state.players.insert(&chain_id, player);
+    async fn store(mut self) {
+        let _ = self.state.save().await;
+    }
+}
+
+impl RouletteContract {
+    /// Rewrites a version 1 chain in the version 2 layout: a host's single game becomes
+    /// table `DEFAULT_TABLE_ID` and every player is seated there.
+    async fn upgrade_from_v1(mut legacy: RouletteStateV1, context: ViewStorageContext) -> RouletteState {
+        let game = legacy.game.get().clone();
+        let mut players = Vec::new();
+        legacy.players.for_each_index_value(|_, player| {
+            players.push(player.into_owned());
+            Ok(())
+        }).await.expect("Failed to read legacy players");
+        let player_chains = legacy.player_chains.indices().await.expect("Failed to read legacy player chains");
+        let is_host = *legacy.is_host.get();
+        let host_chain_id = legacy.host_chain_id.get().clone();
+        let my_player = legacy.my_player.get().clone();
+
+        legacy.clear();
+        legacy.save().await.expect("Failed to clear legacy state");
+
+        let mut state = RouletteState::load(context).await.expect("Failed to load state");
+        // Player chains mirror their host's tables from its events instead of keeping their own.
+        if is_host {
+            state.tables.insert(&DEFAULT_TABLE_ID, game.upgrade()).expect("Failed to migrate table");
+            state.table_configs.insert(&DEFAULT_TABLE_ID, TableConfig::default()).expect("Failed to migrate table");
+            state.next_table_id.set(DEFAULT_TABLE_ID + 1);
+        }
+        for player in players {
+            let chain_id = player.chain_id.clone();
+            state.players.insert(&chain_id, player.upgrade()).expect("Failed to migrate player");
+        }
+        for chain_id in player_chains {
+            state.player_chains.insert(&chain_id).expect("Failed to migrate player chain");
+        }
+        state.is_host.set(is_host);
+        state.host_chain_id.set(host_chain_id);
+        state.my_player.set(my_player.map(PlayerV1::upgrade));
+        state.schema_version.set(2);
+        state
+    }
+
+    /// Version 1 player chains predate the event stream, so an upgraded one subscribes to
+    /// its host's events here; `bind_host` would skip a host that is already recorded.
+    fn follow_upgraded_host(&mut self) {
+        if *self.state.is_host.get() { return; }
+        let Some(host_chain) = self.host_chain() else { return };
+        let application_id = self.runtime.application_id().forget_abi();
+        self.runtime.subscribe_to_events(host_chain, application_id, EVENT_STREAM_NAME.into());
+    }
+
+    /// Upgrades the loaded state one schema version at a time. Version 2 is the
+    /// oldest current-style layout, so for now this only stamps unstamped chains.
+    fn migrate(&mut self) {
+        let version = self.state.layout_version();
+        assert_eq!(version, SCHEMA_VERSION, "No migration from schema version {version}");
+        if *self.state.schema_version.get() != SCHEMA_VERSION {
+            self.state.schema_version.set(SCHEMA_VERSION);
+        }
+    }
+
+    /// Registry chains only add rooms themselves; leases arrive as `RoomRequest` messages.
+    async fn execute_room_operation(&mut self, operation: Operation) {
+        if let Operation::AddRoom { chain_id, name } = operation {
+            if ChainId::from_str(&chain_id).is_ok() && !matches!(self.state.rooms.contains_key(&chain_id).await, Ok(true)) {
+                let _ = self.state.rooms.insert(&chain_id, Room::new(chain_id.clone(), name));
+            }
+        }
+    }
+
+    /// A chain that has not taken any other role can become a registry.
+    fn enable_registry(&mut self) {
+        let state = &self.state;
+        if *state.is_host.get() || *state.is_spectator.get() || state.host_chain_id.get().is_some() || state.my_player.get().is_some() {
+            return;
+        }
+        self.state.is_registry.set(true);
+    }
+
+    fn request_room(&mut self, registry_chain_id: &str, chain_id: String, action: RoomAction) {
+        let Ok(registry_chain) = ChainId::from_str(registry_chain_id) else { return };
+        self.runtime.prepare_message(Message::RoomRequest { chain_id, action }).send_to(registry_chain);
+    }
+
+    /// Applies a lease change for the chain that sent it; rejected changes leave the room as it was.
+    async fn update_room(&mut self, claimant: ChainId, chain_id: String, action: RoomAction) {
+        let now = self.now_ms();
+        let Ok(Some(mut room)) = self.state.rooms.get(&chain_id).await else { return };
+        if room.apply(action, &claimant.to_string(), now).is_ok() {
+            let _ = self.state.rooms.insert(&chain_id, room);
+        }
+    }
+
+    /// Solo chains play every operation locally against their own table, for
+    /// their own player only.
+    async fn execute_solo_operation(&mut self, operation: Operation) {
+        let chain_id = self.runtime.chain_id().to_string();
+        match operation {
+            Operation::RegisterPlayer { table_id, name, initial_balance, .. } => {
+                if self.state.my_player.get().is_some() || self.table_config(table_id).await.is_none() { return; }
+                let player = Player { chain_id: chain_id.clone(), name, balance: initial_balance, table_id };
+                let _ = self.state.players.insert(&chain_id, player.clone());
+                self.state.my_player.set(Some(player.clone()));
+                let timestamp = self.runtime.system_time().micros().to_string();
+                self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::PlayerRegistered { player, timestamp });
+            }
+
+            Operation::PlaceBet { table_id, bet_type, numbers, amount, .. } => {
+                let Some(player_name) = self.state.my_player.get().as_ref().map(|p| p.name.clone()) else { return };
+                let bet = Bet { table_id, player_chain_id: chain_id, player_name, bet_type, numbers, amount };
+                if self.place_bet(&bet).await.is_ok() {
+                    self.sync_solo_player().await;
+                    self.spin_wheel(table_id).await;
                 }
             }
 
-            Message::BalanceUpdate { new_balance, .. } => {
-                let chain_id = self.runtime.chain_id().to_string();
-                if let Ok(Some(mut player)) = self.state.players.get(&chain_id).await {
-                    player.balance = new_balance;
-                    let _ = self.state.players.insert(&chain_id, player);
+            Operation::SpinWheel { table_id } => self.spin_wheel(table_id).await,
+
+            Operation::ConfigureTable { table_id, config } => {
+                if self.table_config(table_id).await.is_some() {
+                    self.update_table_config(table_id, config).await;
                 }
             }
 
-            Message::SpinWheelRequest { .. } => {
-                if !*self.state.is_host.get() { return; }
-                let mut game = self.state.game.get().clone();
-                if game.is_spinning || game.current_bets.is_empty() { return; }
-                game.is_spinning = true;
-                self.state.game.set(game.clone());
-                let result = self.generate_random_number(&game);
-                let timestamp = self.runtime.system_time().micros().to_string();
-                self.settle_bets_internal(result, timestamp).await;
+            Operation::ChangeLimits { change } => {
+                let _ = self.change_limits(&chain_id, change).await;
             }
+
+            _ => {}
         }
     }
 
-    async fn process_streams(&mut self, _streams: Vec<linera_sdk::linera_base_types::StreamUpdate>) {}
+    /// A solo chain keeps its seat in `players` like any host, so `my_player` is copied from it.
+    async fn sync_solo_player(&mut self) {
+        let chain_id = self.runtime.chain_id().to_string();
+        if let Ok(Some(player)) = self.state.players.get(&chain_id).await {
+            self.state.my_player.set(Some(player));
+        }
+    }
 
-    async fn store(mut self) {
-        let _ = self.state.save().await;
+    fn now_ms(&mut self) -> u64 {
+        self.runtime.system_time().micros() / 1000
     }
-}
 
-impl RouletteContract {
-    fn generate_random_number(&mut self, game: &GameState) -> u8 {
+    async fn game(&self, table_id: TableId) -> Option<GameState> {
+        self.state.tables.get(&table_id).await.ok().flatten()
+    }
+
+    async fn table_config(&self, table_id: TableId) -> Option<TableConfig> {
+        self.state.table_configs.get(&table_id).await.ok().flatten()
+    }
+
+    fn create_table(&mut self, config: TableConfig) -> TableId {
+        let table_id = *self.state.next_table_id.get();
+        self.state.next_table_id.set(table_id + 1);
+        let _ = self.state.tables.insert(&table_id, GameState::new());
+        let _ = self.state.table_configs.insert(&table_id, config.clone());
+
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::TableCreated { table_id, config: config.public(), timestamp });
+        table_id
+    }
+
+    /// Builds the lobby summary of every table, counting seated players in one pass.
+    async fn table_infos(&self) -> Vec<TableInfo> {
+        let mut player_counts: HashMap<TableId, u64> = HashMap::new();
+        let _ = self.state.players.for_each_index_value(|_, player| {
+            *player_counts.entry(player.table_id).or_default() += 1;
+            Ok(())
+        }).await;
+
+        let mut tables = Vec::new();
+        for table_id in self.state.table_configs.indices().await.unwrap_or_default() {
+            if let (Some(game), Some(config)) = (self.game(table_id).await, self.table_config(table_id).await) {
+                let player_count = player_counts.get(&table_id).copied().unwrap_or(0);
+                tables.push(TableInfo::new(table_id, config, &game, player_count));
+            }
+        }
+        tables
+    }
+
+    async fn announce_tables(&mut self) {
+        if *self.state.is_solo.get() { return; }
+        let Some(registry_chain) = self.state.registry_chain_id.get().as_deref().and_then(|id| ChainId::from_str(id).ok()) else { return };
+        let tables = self.table_infos().await;
+        self.runtime
+            .prepare_message(Message::AnnounceTables { tables })
+            .with_tracking()
+            .send_to(registry_chain);
+    }
+
+    /// Rebinds a player chain to another host, releasing its chips from the current one first.
+    async fn join_table(&mut self, host_chain_id: String, table_id: TableId, invite_code: Option<String>) {
+        if ChainId::from_str(&host_chain_id).is_err() { return; }
+        let current_host = self.state.host_chain_id.get().as_deref().and_then(|id| ChainId::from_str(id).ok());
+        match (self.state.my_player.get().is_some(), current_host) {
+            (true, Some(old_host)) => {
+                let seated_at = self.state.my_player.get().as_ref().map_or(DEFAULT_TABLE_ID, |p| p.table_id);
+                self.state.pending_join.set(Some(PendingJoin { host_chain_id, table_id, invite_code }));
+                let request_id = self.track_request(RequestKind::Leave, seated_at, 0);
+                self.runtime
+                    .prepare_message(Message::LeaveTableRequest { request_id })
+                    .with_tracking()
+                    .send_to(old_host);
+            }
+            (seated, _) => {
+                let Ok(host_chain) = ChainId::from_str(&host_chain_id) else { return };
+                self.bind_host(host_chain_id);
+                // Chips left in the wallet by an earlier table come along to the new one. With an
+                // empty wallet the player registers with `RegisterPlayer` as usual.
+                let balance = *self.state.wallet_balance.get();
+                if !seated && balance > 0 {
+                    // The old seat's name went with it, so the chain id stands in for one.
+                    let name = self.runtime.chain_id().to_string();
+                    self.request_seat(host_chain, table_id, name, balance, invite_code);
+                }
+            }
+        }
+    }
+
+    /// Asks `host_chain` for a seat paid for with `balance`. The wallet keeps the chips until
+    /// the host confirms the seat.
+    fn request_seat(&mut self, host_chain: ChainId, table_id: TableId, name: String, balance: u64, invite_code: Option<String>) {
+        let request_id = self.track_request(RequestKind::Register, table_id, balance);
+        self.runtime
+            .prepare_message(Message::RegisterPlayerRequest { request_id, table_id, name, initial_balance: balance, invite_code })
+            .with_tracking()
+            .send_to(host_chain);
+    }
+
+    fn host_chain(&self) -> Option<ChainId> {
+        self.state.host_chain_id.get().as_deref().and_then(|id| ChainId::from_str(id).ok())
+    }
+
+    /// Points a player chain at a host and moves its event subscription there.
+    fn bind_host(&mut self, host_chain_id: String) {
+        let Ok(new_host) = ChainId::from_str(&host_chain_id) else { return };
+        let old_host = self.host_chain();
+        if old_host == Some(new_host) { return; }
+
+        let application_id = self.runtime.application_id().forget_abi();
+        if let Some(old_host) = old_host {
+            self.runtime.unsubscribe_from_events(old_host, application_id, EVENT_STREAM_NAME.into());
+            self.state.tables.clear();
+            self.state.table_configs.clear();
+        }
+        self.runtime.subscribe_to_events(new_host, application_id, EVENT_STREAM_NAME.into());
+        self.state.host_chain_id.set(Some(host_chain_id));
+    }
+
+    /// Updates the local mirror of the host's tables from one of its events.
+    async fn mirror_event(&mut self, event: RouletteEvent) {
+        let table_id = event.table_id();
+        if let RouletteEvent::TableCreated { config, .. } | RouletteEvent::TableConfigured { config, .. } = &event {
+            let _ = self.state.table_configs.insert(&table_id, config.clone());
+        }
+        let mut game = self.game(table_id).await.unwrap_or_default();
+        game.apply_event(&event);
+        let _ = self.state.tables.insert(&table_id, game);
+    }
+
+    async fn update_table_config(&mut self, table_id: TableId, config: TableConfig) {
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::TableConfigured { table_id, config: config.public(), timestamp });
+        let _ = self.state.table_configs.insert(&table_id, config);
+        self.announce_tables().await;
+    }
+
+    /// Unseats a player, applying the table's leave policy to their open bets and sending the
+    /// remaining balance back to their chain. Returns `false` if the player is not registered.
+    async fn remove_player(&mut self, chain_id: &str, kicked: bool, request_id: Option<RequestId>) -> bool {
+        let mut player = match self.state.players.get(chain_id).await {
+            Ok(Some(p)) => p,
+            _ => return false,
+        };
+
+        let mut refunded = 0;
+        let mut forfeited = 0;
+        let table_id = player.table_id;
+        if let (Some(mut game), Some(config)) = (self.game(table_id).await, self.table_config(table_id).await) {
+            // The bets leave the table either way, so a later seat for the same chain is not paid for them.
+            let open_amount = game.take_bets_of(chain_id);
+            let _ = self.state.tables.insert(&table_id, game);
+            match config.leave_policy {
+                LeavePolicy::Refund => {
+                    refunded = open_amount;
+                    player.balance += open_amount;
+                    self.record_return(chain_id, ResponsibleGaming::record_refund, open_amount).await;
+                }
+                LeavePolicy::Forfeit => {
+                    forfeited = open_amount;
+                    self.keep_forfeited(open_amount);
+                }
+            }
+        }
+
+        let _ = self.state.players.remove(chain_id);
+        let _ = self.state.player_chains.remove(chain_id);
+
+        if let Ok(player_chain) = ChainId::from_str(chain_id) {
+            self.runtime
+                .prepare_message(Message::LeaveTableConfirm { request_id, player: player.clone(), success: true, kicked, error_message: None })
+                .with_tracking()
+                .send_to(player_chain);
+        }
+
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::PlayerLeft {
+            player,
+            refunded,
+            forfeited,
+            kicked,
+            timestamp,
+        });
+        self.announce_tables().await;
+        true
+    }
+
+    /// Refunds open bets, unseats everyone at the table and starts a new session there.
+    async fn reset_table(&mut self, table_id: TableId, notify_players: bool) {
+        let Some(game) = self.game(table_id).await else { return };
+        let stakes = game.open_stakes();
+        let mut seated = Vec::new();
+        let _ = self.state.players.for_each_index_value(|_, player| {
+            if player.table_id == table_id { seated.push(player.into_owned()); }
+            Ok(())
+        }).await;
+
+        let (mut refunded, mut paid_out, mut archived) = (0, 0, 0);
+        for mut player in seated {
+            let stake = stakes.get(&player.chain_id).copied().unwrap_or(0);
+            refunded += stake;
+            player.balance += stake;
+            self.record_return(&player.chain_id, ResponsibleGaming::record_refund, stake).await;
+            let _ = self.state.players.remove(&player.chain_id);
+            let _ = self.state.player_chains.remove(&player.chain_id);
+            match ChainId::from_str(&player.chain_id) {
+                Ok(player_chain) if notify_players => {
+                    paid_out += player.balance;
+                    self.runtime
+                        .prepare_message(Message::LeaveTableConfirm {
+                            request_id: None,
+                            player,
+                            success: true,
+                            kicked: true,
+                            error_message: Some("Table reset".to_string()),
+                        })
+                        .with_tracking()
+                        .send_to(player_chain);
+                }
+                _ => {
+                    archived += player.balance;
+                    let previous = self.state.archived_balances.get(&player.chain_id).await.ok().flatten().unwrap_or(0);
+                    let _ = self.state.archived_balances.insert(&player.chain_id, previous + player.balance);
+                }
+            }
+        }
+
+        let session_id = self.state.table_sessions.get(&table_id).await.ok().flatten().unwrap_or(0) + 1;
+        let _ = self.state.table_sessions.insert(&table_id, session_id);
+        let _ = self.state.tables.insert(&table_id, GameState::new());
+
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::TableReset { table_id, session_id, refunded, paid_out, archived, timestamp });
+        self.announce_tables().await;
+    }
+
+    fn keep_forfeited(&mut self, amount: u64) {
+        let house_funds = *self.state.house_funds.get();
+        self.state.house_funds.set(house_funds.saturating_add(amount));
+    }
+
+    async fn player_balance(&self, chain_id: &str) -> u64 {
+        self.state.players.get(chain_id).await.ok().flatten().map_or(0, |p| p.balance)
+    }
+
+    async fn responsible_gaming(&self, chain_id: &str) -> ResponsibleGaming {
+        self.state.responsible_gaming.get(chain_id).await.ok().flatten().unwrap_or_default()
+    }
+
+    async fn change_limits(&mut self, chain_id: &str, change: LimitChange) -> (ResponsibleGaming, Result<(), LimitError>) {
+        let now = self.now_ms();
+        let mut gaming = self.responsible_gaming(chain_id).await;
+        let result = gaming.apply(change, now);
+        if result.is_ok() {
+            let _ = self.state.responsible_gaming.insert(chain_id, gaming.clone());
+        }
+        (gaming, result)
+    }
+
+    /// Counts chips handed back to a player towards their loss limits.
+    async fn record_return(&mut self, chain_id: &str, record: fn(&mut ResponsibleGaming, u64, u64), amount: u64) {
+        if amount == 0 { return; }
+        let now = self.now_ms();
+        let mut gaming = self.responsible_gaming(chain_id).await;
+        record(&mut gaming, amount, now);
+        let _ = self.state.responsible_gaming.insert(chain_id, gaming);
+    }
+
+    /// Validates a bet against the player and table, then debits the stake and adds it to the pot.
+    async fn place_bet(&mut self, bet: &Bet) -> Result<u64, BetError> {
+        let player = self.state.players.get(&bet.player_chain_id).await.ok().flatten();
+        let game = self.game(bet.table_id).await;
+        let config = self.table_config(bet.table_id).await;
+        let new_balance = validate_bet(
+            player.as_ref(),
+            game.as_ref().zip(config.as_ref()),
+            bet.table_id,
+            bet.bet_type,
+            &bet.numbers,
+            bet.amount,
+        )?;
+        let (Some(mut player), Some(mut game)) = (player, game) else { return Err(BetError::UnknownTable) };
+        let now = self.now_ms();
+        let mut gaming = self.responsible_gaming(&bet.player_chain_id).await;
+        gaming.check_stake(bet.amount, now)?;
+        gaming.record_stake(bet.amount, now);
+        let _ = self.state.responsible_gaming.insert(&bet.player_chain_id, gaming);
+
+        player.balance = new_balance;
+        let _ = self.state.players.insert(&bet.player_chain_id, player);
+        game.current_bets.push(bet.clone());
+        let _ = self.state.tables.insert(&bet.table_id, game);
+
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::BetPlaced { bet: bet.clone(), timestamp });
+        Ok(new_balance)
+    }
+
+    fn reject_bet(&mut self, player_chain: ChainId, request_id: RequestId, bet: Bet, balance: u64, error_message: String) {
+        self.runtime
+            .prepare_message(Message::BetPlacedConfirm {
+                request_id,
+                bet,
+                success: false,
+                new_balance: balance,
+                error_message: Some(error_message),
+            })
+            .with_tracking()
+            .send_to(player_chain);
+    }
+
+    /// Applies a balance the host reported for this player chain.
+    async fn set_my_balance(&mut self, new_balance: u64) {
+        let chain_id = self.runtime.chain_id().to_string();
+        if let Ok(Some(mut player)) = self.state.players.get(&chain_id).await {
+            player.balance = new_balance;
+            let _ = self.state.players.insert(&chain_id, player);
+        }
+        if let Some(player) = self.state.my_player.get_mut() {
+            player.balance = new_balance;
+        }
+    }
+
+    fn send_bet(&mut self, host_chain: ChainId, table_id: TableId, bet_type: BetType, numbers: Vec<u8>, amount: u64) {
+        let player_name = self.state.my_player.get()
+            .as_ref()
+            .map(|p| p.name.clone())
+            .unwrap_or_default();
+        let request_id = self.track_request(RequestKind::PlaceBet, table_id, amount);
+        self.runtime
+            .prepare_message(Message::PlaceBetRequest { request_id, table_id, bet_type, numbers, amount, player_name })
+            .with_tracking()
+            .send_to(host_chain);
+    }
+
+    /// Places the autoplay layout for the next round, unless a limit stops it at `balance`.
+    fn play_autoplay_round(&mut self, balance: u64) {
+        let Some(host_chain) = self.host_chain() else { return };
+        let Some(autoplay) = self.state.autoplay.get_mut() else { return };
+        if !autoplay.can_continue(balance) { return; }
+        let (table_id, bets) = (autoplay.table_id, autoplay.bets.clone());
+        self.send_bets(host_chain, table_id, bets);
+    }
+
+    fn send_bets(&mut self, host_chain: ChainId, table_id: TableId, bets: Vec<BetLayout>) {
+        for bet in bets {
+            self.send_bet(host_chain, table_id, bet.bet_type, bet.numbers, bet.amount);
+        }
+    }
+
+    /// Autoplay and strategies each bet every round, so only one may run at a time.
+    fn is_betting_automatically(&self) -> bool {
+        self.state.autoplay.get().as_ref().is_some_and(Autoplay::is_active)
+            || self.state.strategy.get().as_ref().is_some_and(StrategyRun::is_active)
+    }
+
+    /// Player: drops this chain's seat and stops anything betting on it. The wallet is untouched.
+    fn unseat(&mut self) {
+        let chain_id = self.runtime.chain_id().to_string();
+        let _ = self.state.players.remove(&chain_id);
+        self.state.my_player.set(None);
+        self.stop_autoplay(AutoplayStop::Unseated);
+        self.stop_strategy(StrategyStop::Unseated);
+    }
+
+    fn stop_autoplay(&mut self, reason: AutoplayStop) {
+        if let Some(autoplay) = self.state.autoplay.get_mut() {
+            autoplay.stop(reason);
+        }
+    }
+
+    fn stop_strategy(&mut self, reason: StrategyStop) {
+        if let Some(run) = self.state.strategy.get_mut() {
+            run.stop(reason);
+        }
+    }
+
+    /// Whether the mirror of `table_id` holds any of this chain's bets.
+    async fn has_open_bets(&mut self, table_id: TableId) -> bool {
+        let chain_id = self.runtime.chain_id().to_string();
+        self.game(table_id).await.is_some_and(|game| game.current_bets.iter().any(|bet| bet.player_chain_id == chain_id))
+    }
+
+    fn request_balance_snapshot(&mut self, host_chain: ChainId) {
+        let request_id = self.next_request_id();
+        self.runtime
+            .prepare_message(Message::BalanceSnapshotRequest { request_id })
+            .with_tracking()
+            .send_to(host_chain);
+    }
+
+    fn next_request_id(&mut self) -> RequestId {
+        let request_id = *self.state.next_request_id.get();
+        self.state.next_request_id.set(request_id + 1);
+        request_id
+    }
+
+    /// Records a request about to be sent to the host and returns its id.
+    fn track_request(&mut self, kind: RequestKind, table_id: TableId, amount: u64) -> RequestId {
+        let request_id = self.next_request_id();
+        let sent_at = self.now_ms();
+        let _ = self.state.pending_requests.insert(&request_id, PendingRequest { request_id, kind, table_id, amount, sent_at });
+        request_id
+    }
+
+    /// Drops a pending request once answered, remembering the error if it failed.
+    async fn resolve_request(&mut self, request_id: RequestId, error_message: Option<String>) -> Option<PendingRequest> {
+        let request = self.state.pending_requests.get(&request_id).await.ok().flatten()?;
+        let _ = self.state.pending_requests.remove(&request_id);
+        if let Some(error_message) = error_message {
+            let error = RequestError { request_id, kind: request.kind, error_message, timestamp: self.now_ms() };
+            push_request_error(self.state.last_errors.get_mut(), error);
+        }
+        Some(request)
+    }
+
+    /// Gives up on requests the host has not answered within `REQUEST_TIMEOUT_MS`.
+    async fn expire_requests(&mut self) {
+        let now = self.now_ms();
+        let mut expired = Vec::new();
+        let _ = self.state.pending_requests.for_each_index_value(|request_id, request| {
+            if request.is_expired(now) { expired.push(request_id); }
+            Ok(())
+        }).await;
+        for request_id in expired {
+            let request = self.resolve_request(request_id, Some("Timed out waiting for host".to_string())).await;
+            // A late confirmation would be ignored, so the table switch cannot go ahead.
+            if request.is_some_and(|request| request.kind == RequestKind::Leave) {
+                self.state.pending_join.set(None);
+            }
+        }
+    }
+
+    /// Host side: accepts each player request at most once and in order.
+    async fn check_request_nonce(&mut self, sender_chain: ChainId, request_id: RequestId) -> Result<(), NonceError> {
+        let sender = sender_chain.to_string();
+        let last = self.state.player_nonces.get(&sender).await.ok().flatten();
+        check_nonce(last, request_id)?;
+        let _ = self.state.player_nonces.insert(&sender, request_id);
+        Ok(())
+    }
+
+    /// Answers a replayed request without acting on it. Spins and leaves have
+    /// no failure reply: a late one must not undo the state the original set.
+    fn reject_replay(&mut self, sender_chain: ChainId, message: Message, error: &NonceError) {
+        match message {
+            Message::RegisterPlayerRequest { request_id, table_id, name, .. } => {
+                self.runtime
+                    .prepare_message(Message::PlayerRegisteredConfirm {
+                        request_id,
+                        player: Player { chain_id: sender_chain.to_string(), name, balance: 0, table_id },
+                        success: false,
+                        error_message: Some(error.to_string()),
+                    })
+                    .with_tracking()
+                    .send_to(sender_chain);
+            }
+            Message::PlaceBetRequest { request_id, table_id, bet_type, numbers, amount, player_name } => {
+                let bet = Bet { table_id, player_chain_id: sender_chain.to_string(), player_name, bet_type, numbers, amount };
+                self.reject_bet(sender_chain, request_id, bet, 0, error.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    /// Compensates for one of our own messages that `target` rejected.
+    async fn handle_bounce(&mut self, target: ChainId, message: Message) {
+        match bounce_action(&message) {
+            BounceAction::FailRequest(request_id) => {
+                self.resolve_request(request_id, Some("Rejected by host chain".to_string())).await;
+            }
+            BounceAction::CancelLeave(request_id) => {
+                self.resolve_request(request_id, Some("Rejected by host chain".to_string())).await;
+                self.state.pending_join.set(None);
+            }
+            BounceAction::ReleaseSeat(chain_id) => self.release_seat(&chain_id).await,
+            BounceAction::RefundBet(bet) => self.refund_bet(bet).await,
+            BounceAction::Retry => {
+                let target = target.to_string();
+                let mut queue = self.state.undelivered.get(&target).await.ok().flatten().unwrap_or_default();
+                if queue.push(message) {
+                    let _ = self.state.undelivered.insert(&target, queue);
+                } else {
+                    let _ = self.state.undelivered.remove(&target);
+                }
+            }
+            BounceAction::Ignore => {}
+        }
+    }
+
+    /// Resends what bounced messages owed, built from current state rather than replayed: balances
+    /// go out as one fresh snapshot, and a leave confirm is dropped once the player is seated again.
+    /// Each further bounce counts against the chain.
+    async fn retry_deliveries(&mut self) {
+        let targets = self.state.undelivered.indices().await.unwrap_or_default();
+        for target in targets {
+            let Ok(Some(mut queue)) = self.state.undelivered.get(&target).await else { continue };
+            if queue.messages.is_empty() { continue; }
+            let Ok(target_chain) = ChainId::from_str(&target) else { continue };
+            let seated = self.state.players.get(&target).await.ok().flatten();
+            let mut snapshot_sent = false;
+            for message in std::mem::take(&mut queue.messages) {
+                let message = match message {
+                    Message::SpinResultBroadcast { .. } | Message::BalanceUpdate { .. } => {
+                        if std::mem::replace(&mut snapshot_sent, true) { continue; }
+                        Message::BalanceSnapshot { request_id: 0, player: seated.clone() }
+                    }
+                    Message::LeaveTableConfirm { .. } if seated.is_some() => continue,
+                    Message::LimitsConfirm { request_id, .. } => {
+                        let settings = self.responsible_gaming(&target).await;
+                        Message::LimitsConfirm { request_id, settings, error_message: None }
+                    }
+                    message => message,
+                };
+                self.runtime.prepare_message(message).with_tracking().send_to(target_chain);
+            }
+            let _ = self.state.undelivered.insert(&target, queue);
+        }
+    }
+
+    /// Drops a registration the player chain never accepted; its open bets are forfeited.
+    async fn release_seat(&mut self, chain_id: &str) {
+        let Ok(Some(player)) = self.state.players.get(chain_id).await else { return };
+        let mut forfeited = 0;
+        if let Some(mut game) = self.game(player.table_id).await {
+            forfeited = game.take_bets_of(chain_id);
+            let _ = self.state.tables.insert(&player.table_id, game);
+            self.keep_forfeited(forfeited);
+        }
+        let _ = self.state.players.remove(chain_id);
+        let _ = self.state.player_chains.remove(chain_id);
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::PlayerLeft { player, refunded: 0, forfeited, kicked: false, timestamp });
+        self.announce_tables().await;
+    }
+
+    /// Takes back a bet the player chain never acknowledged, if its round is still open.
+    async fn refund_bet(&mut self, bet: Bet) {
+        let Some(mut game) = self.game(bet.table_id).await else { return };
+        if !game.withdraw_bet(&bet) { return; }
+        let _ = self.state.tables.insert(&bet.table_id, game);
+        if let Ok(Some(mut player)) = self.state.players.get(&bet.player_chain_id).await {
+            player.balance += bet.amount;
+            let _ = self.state.players.insert(&bet.player_chain_id, player);
+        }
+        self.record_return(&bet.player_chain_id, ResponsibleGaming::record_refund, bet.amount).await;
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::BetRefunded { bet, timestamp });
+    }
+
+    /// Applies the scheduled round transition, if any, on every table that auto-advances.
+    async fn advance_rounds(&mut self) {
+        let table_ids = self.state.table_configs.indices().await.unwrap_or_default();
+        for table_id in table_ids {
+            self.advance_round(table_id).await;
+        }
+    }
+
+    async fn advance_round(&mut self, table_id: TableId) {
+        let now_ms = self.now_ms();
+        let (Some(mut game), Some(config)) = (self.game(table_id).await, self.table_config(table_id).await) else { return };
+        match game.due_action(now_ms, &config.round) {
+            Some(RoundAction::StartRound) => self.start_round(table_id).await,
+            Some(RoundAction::CloseBetting) => {
+                if game.current_bets.is_empty() {
+                    let next_round_time = now_ms + config.round.cooldown_ms;
+                    game.betting_end_time = None;
+                    game.next_round_time = Some(next_round_time);
+                    let _ = self.state.tables.insert(&table_id, game);
+                    let timestamp = self.runtime.system_time().micros().to_string();
+                    self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::RoundSkipped { table_id, next_round_time, timestamp });
+                } else {
+                    self.spin_wheel(table_id).await;
+                }
+            }
+            None => {}
+        }
+    }
+
+    async fn start_round(&mut self, table_id: TableId) {
+        let (Some(mut game), Some(config)) = (self.game(table_id).await, self.table_config(table_id).await) else { return };
+        let betting_end_time = self.now_ms() + config.round.betting_duration_ms;
+        game.betting_end_time = Some(betting_end_time);
+        game.next_round_time = None;
+        let _ = self.state.tables.insert(&table_id, game);
+
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::RoundStarted { table_id, betting_end_time, timestamp });
+    }
+
+    async fn spin_wheel(&mut self, table_id: TableId) {
+        let (Some(mut game), Some(config)) = (self.game(table_id).await, self.table_config(table_id).await) else { return };
+        if game.is_spinning || game.current_bets.is_empty() { return; }
+        game.is_spinning = true;
+        let _ = self.state.tables.insert(&table_id, game.clone());
+        let result = self.generate_random_number(&game, config.variant.pocket_count());
+        let timestamp = self.runtime.system_time().micros().to_string();
+        self.settle_bets_internal(table_id, result, timestamp).await;
+    }
+
+    fn generate_random_number(&mut self, game: &GameState, pocket_count: u8) -> u8 {
         let mut hasher = Sha256::new();
         hasher.update(self.runtime.system_time().micros().to_le_bytes());
         hasher.update(self.runtime.chain_id().to_string().as_bytes());
@@ -323,74 +1297,126 @@ impl RouletteContract {
         hasher.update((game.current_bets.len() as u64).to_le_bytes());
         let hash = hasher.finalize();
         let bytes: [u8; 8] = hash[0..8].try_into().unwrap_or([0u8; 8]);
-        (u64::from_le_bytes(bytes) % 37) as u8
+        (u64::from_le_bytes(bytes) % pocket_count as u64) as u8
     }
 
-    async fn settle_bets_internal(&mut self, result: u8, timestamp: String) {
-        let mut game = self.state.game.get().clone();
-        let color = get_color(result);
-        let mut winners = Vec::new();
-        let mut player_payouts: HashMap<String, (u64, u64, bool)> = HashMap::new();
-
+    async fn settle_bets_internal(&mut self, table_id: TableId, result: u8, timestamp: String) {
+        let mut game = self.game(table_id).await.unwrap_or_default();
+        let cooldown_ms = self.table_config(table_id).await.unwrap_or_default().round.cooldown_ms;
+        let mut balances = BTreeMap::new();
         for bet in &game.current_bets {
-            player_payouts.entry(bet.player_chain_id.clone()).or_insert((0, 0, false));
+            // Only seated players are paid.
+            if let Ok(Some(player)) = self.state.players.get(&bet.player_chain_id).await {
+                balances.insert(bet.player_chain_id.clone(), player.balance);
+            }
         }
-
-        for bet in &game.current_bets {
-            let is_winner = bet.bet_type.is_winner(result, &bet.numbers);
-            let payout = if is_winner { bet.amount + (bet.amount * bet.bet_type.payout_multiplier()) } else { 0 };
-
-            if let Ok(Some(mut player)) = self.state.players.get(&bet.player_chain_id).await {
-                if is_winner {
-                    player.balance += payout;
-                    let _ = self.state.players.insert(&bet.player_chain_id, player.clone());
-                    winners.push(Winner {
-                        player_chain_id: bet.player_chain_id.clone(),
-                        player_name: bet.player_name.clone(),
-                        bet_type: bet.bet_type,
-                        bet_amount: bet.amount,
-                        payout,
-                    });
-                }
-                if let Some(entry) = player_payouts.get_mut(&bet.player_chain_id) {
-                    entry.0 += payout;
-                    entry.1 = player.balance;
-                    if is_winner { entry.2 = true; }
-                }
+        let settlement = settle_bets(&game.current_bets, result, &balances);
+        for (player_chain_id, payout) in &settlement.payouts {
+            if let Ok(Some(mut player)) = self.state.players.get(player_chain_id).await {
+                player.balance = payout.new_balance;
+                let _ = self.state.players.insert(player_chain_id, player);
             }
+            self.record_return(player_chain_id, ResponsibleGaming::record_payout, payout.payout).await;
         }
 
-        let spin_result = SpinResult { number: result, color, timestamp: timestamp.clone(), winners };
-
-        game.history.push(result);
-        if game.history.len() > 10 { game.history.remove(0); }
+        let spin_result = SpinResult { number: result, color: get_color(result), timestamp: timestamp.clone(), winners: settlement.winners };
 
-        let bets_to_broadcast = game.current_bets.clone();
-        game.current_bets.clear();
-        game.last_result = Some(spin_result.clone());
-        game.is_spinning = false;
-        game.betting_end_time = None;
-        self.state.game.set(game);
+        game.record_result(spin_result.clone());
+        game.next_round_time = Some(self.now_ms() + cooldown_ms);
+        let _ = self.state.tables.insert(&table_id, game);
 
-        for bet in &bets_to_broadcast {
-            if let Ok(player_chain) = ChainId::from_str(&bet.player_chain_id) {
-                let (payout, new_balance, won) = player_payouts.get(&bet.player_chain_id).cloned().unwrap_or((0, 0, false));
-                self.runtime
-                    .prepare_message(Message::SpinResultBroadcast {
-                        result: spin_result.clone(),
-                        player_payout: payout,
-                        new_balance,
-                        won,
-                    })
-                    .with_tracking()
-                    .send_to(player_chain);
+        if *self.state.is_solo.get() {
+            self.sync_solo_player().await;
+        } else {
+            for (player_chain_id, PlayerPayout { payout, new_balance, won }) in settlement.payouts {
+                if let Ok(player_chain) = ChainId::from_str(&player_chain_id) {
+                    self.runtime
+                        .prepare_message(Message::SpinResultBroadcast {
+                            table_id,
+                            number: result,
+                            player_payout: payout,
+                            new_balance,
+                            won,
+                        })
+                        .with_tracking()
+                        .send_to(player_chain);
+                }
             }
         }
 
-        self.runtime.emit("roulette_events".into(), &linera_roulette::RouletteEvent::WheelSpun {
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::WheelSpun {
+            table_id,
             result: spin_result,
             timestamp: timestamp.clone(),
         });
-        self.runtime.emit("roulette_events".into(), &linera_roulette::RouletteEvent::BetsSettled { timestamp });
+        self.runtime.emit(EVENT_STREAM_NAME.into(), &RouletteEvent::BetsSettled { table_id, timestamp });
+        self.announce_tables().await;
+    }
+}
+
+#[cfg(all(test, feature = "integration"))]
+mod tests {
+    use super::*;
+    use linera_roulette::{BetV1, GameStateV1};
+    use linera_sdk::linera_base_types::{ApplicationId, CryptoHash};
+
+    fn player_v1(chain_id: &str) -> PlayerV1 {
+        PlayerV1 { chain_id: chain_id.to_string(), name: "Al".to_string(), balance: 100 }
+    }
+
+    /// A mock runtime whose storage holds a version 1 chain, written through its views.
+    async fn version_1_chain(is_host: bool, host_chain_id: Option<String>) -> ContractRuntime<RouletteContract> {
+        let runtime = ContractRuntime::new()
+            .with_application_id(ApplicationId::new(CryptoHash::test_hash("roulette")).with_abi());
+        let mut legacy = RouletteStateV1::load(runtime.root_view_storage_context()).await.unwrap();
+        let bet = BetV1 { player_chain_id: "c1".to_string(), player_name: "Al".to_string(), bet_type: BetType::Red, numbers: vec![], amount: 10 };
+        legacy.game.set(GameStateV1 { current_bets: vec![bet], history: vec![7], ..GameStateV1::default() });
+        legacy.players.insert("c1", player_v1("c1")).unwrap();
+        legacy.player_chains.insert("c1").unwrap();
+        legacy.is_host.set(is_host);
+        legacy.host_chain_id.set(host_chain_id);
+        legacy.my_player.set((!is_host).then(|| player_v1("c1")));
+        legacy.save().await.unwrap();
+        runtime
+    }
+
+    #[tokio::test]
+    async fn test_stored_version_1_state_loads_as_legacy() {
+        let runtime = version_1_chain(true, None).await;
+        let Err(legacy) = RouletteState::load_current(runtime.root_view_storage_context()).await else {
+            panic!("a version 1 layout loaded as the current one");
+        };
+        assert!(*legacy.is_host.get());
+        assert_eq!(legacy.game.get().history, vec![7]);
+        assert_eq!(legacy.players.get("c1").await.unwrap().unwrap().balance, 100);
+    }
+
+    #[tokio::test]
+    async fn test_version_1_host_keeps_its_table() {
+        let runtime = version_1_chain(true, None).await;
+        let context = runtime.root_view_storage_context();
+        let contract = RouletteContract::load(runtime).await;
+        let game = contract.game(DEFAULT_TABLE_ID).await.unwrap();
+        assert_eq!(game.history, vec![7]);
+        assert_eq!(game.current_bets.len(), 1);
+        assert_eq!(contract.state.players.get("c1").await.unwrap().unwrap().table_id, DEFAULT_TABLE_ID);
+        assert_eq!(*contract.state.next_table_id.get(), DEFAULT_TABLE_ID + 1);
+
+        contract.store().await;
+        let state = RouletteState::load_current(context).await.ok().unwrap();
+        assert_eq!(*state.schema_version.get(), SCHEMA_VERSION);
+        assert!(state.tables.contains_key(&DEFAULT_TABLE_ID).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_version_1_player_opens_no_table() {
+        let host_chain_id = ChainId(CryptoHash::test_hash("host")).to_string();
+        let runtime = version_1_chain(false, Some(host_chain_id.clone())).await;
+        let contract = RouletteContract::load(runtime).await;
+        assert!(contract.state.tables.indices().await.unwrap().is_empty());
+        assert!(contract.state.table_configs.indices().await.unwrap().is_empty());
+        assert_eq!(contract.state.host_chain_id.get().as_ref(), Some(&host_chain_id));
+        assert_eq!(contract.state.my_player.get().as_ref().map(|p| p.balance), Some(100));
+        assert_eq!(*contract.state.schema_version.get(), SCHEMA_VERSION);
     }
 }
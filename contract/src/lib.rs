@@ -3,6 +3,12 @@
 use async_graphql::{Request, Response};
 use linera_sdk::linera_base_types::{ContractAbi, ServiceAbi};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+pub mod limits;
+pub mod simulation;
+pub mod strategy;
 
 pub struct RouletteAbi;
 
@@ -16,12 +22,49 @@ impl ServiceAbi for RouletteAbi {
     type QueryResponse = Response;
 }
 
+pub type TableId = u32;
+
+/// The table every host chain opens at instantiation.
+pub const DEFAULT_TABLE_ID: TableId = 0;
+
+/// Pocket number used for "00" on double-zero wheels.
+pub const DOUBLE_ZERO: u8 = 37;
+
+/// Stream the host publishes public table activity on.
+pub const EVENT_STREAM_NAME: &str = "roulette_events";
+
+/// Number of recent results kept in `GameState::history`.
+pub const HISTORY_LEN: usize = 10;
+
+/// Layout version of the stored state; see `RouletteContract::migrate`.
+/// 1 is the original single-table layout, 2 the multi-table one.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Number of failed requests kept for the `lastErrors` query.
+pub const LAST_ERRORS_LEN: usize = 10;
+
+/// How long a player chain waits for the host to answer a request.
+pub const REQUEST_TIMEOUT_MS: u64 = 60_000;
+
+/// Sequence number of a player-to-host message. It correlates a request with its
+/// confirmation and, since it only grows on each player chain, doubles as the
+/// host's replay nonce.
+pub type RequestId = u64;
+
+/// How long a claimed room stays reserved without a heartbeat.
+pub const ROOM_LEASE_MS: u64 = 120_000;
+
+/// Bounces tolerated per player chain before queued deliveries to it are dropped.
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
 pub struct Player {
     pub chain_id: String,
     pub name: String,
     pub balance: u64,
+    #[serde(default)]
+    pub table_id: TableId,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
@@ -41,9 +84,11 @@ pub enum BetType {
     ThirdColumn,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
 pub struct Bet {
+    #[serde(default)]
+    pub table_id: TableId,
     pub player_chain_id: String,
     pub player_name: String,
     pub bet_type: BetType,
@@ -83,48 +128,837 @@ pub struct GameState {
     pub history: Vec<u8>,
     #[serde(default)]
     pub betting_end_time: Option<u64>,
+    #[serde(default)]
+    pub next_round_time: Option<u64>,
 }
 
 impl GameState {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Closes the round with `result`: records it in the history and clears the bets.
+    pub fn record_result(&mut self, result: SpinResult) {
+        self.history.push(result.number);
+        if self.history.len() > HISTORY_LEN { self.history.remove(0); }
+        self.current_bets.clear();
+        self.last_result = Some(result);
+        self.is_spinning = false;
+        self.betting_end_time = None;
+    }
+
+    /// Mirrors a host event onto a local copy of the table.
+    pub fn apply_event(&mut self, event: &RouletteEvent) {
+        match event {
+            RouletteEvent::RoundStarted { betting_end_time, .. } => {
+                self.betting_end_time = Some(*betting_end_time);
+                self.next_round_time = None;
+            }
+            RouletteEvent::BetPlaced { bet, .. } => self.current_bets.push(bet.clone()),
+            RouletteEvent::BetRefunded { bet, .. } => { self.withdraw_bet(bet); }
+            RouletteEvent::TableReset { .. } => *self = GameState::new(),
+            RouletteEvent::WheelSpun { result, .. } => self.record_result(result.clone()),
+            RouletteEvent::PlayerLeft { player, .. } => { self.take_bets_of(&player.chain_id); }
+            RouletteEvent::RoundSkipped { next_round_time, .. } => {
+                self.betting_end_time = None;
+                self.next_round_time = Some(*next_round_time);
+            }
+            _ => {}
+        }
+    }
+
+    /// Total open stake per player chain in the current round.
+    pub fn open_stakes(&self) -> std::collections::BTreeMap<String, u64> {
+        let mut stakes = std::collections::BTreeMap::new();
+        for bet in &self.current_bets {
+            *stakes.entry(bet.player_chain_id.clone()).or_insert(0) += bet.amount;
+        }
+        stakes
+    }
+
+    /// Takes every open bet of `chain_id` off the table and returns their total stake.
+    pub fn take_bets_of(&mut self, chain_id: &str) -> u64 {
+        let stake = self.current_bets.iter().filter(|b| b.player_chain_id == chain_id).map(|b| b.amount).sum();
+        self.current_bets.retain(|b| b.player_chain_id != chain_id);
+        stake
+    }
+
+    /// Takes one matching bet back out of the open round; false if it is not there.
+    pub fn withdraw_bet(&mut self, bet: &Bet) -> bool {
+        if self.is_spinning { return false; }
+        match self.current_bets.iter().position(|b| b == bet) {
+            Some(index) => {
+                self.current_bets.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the round transition that is due at `now_ms`, if the table auto-advances.
+    pub fn due_action(&self, now_ms: u64, config: &RoundConfig) -> Option<RoundAction> {
+        if !config.auto_advance || self.is_spinning {
+            return None;
+        }
+        match (self.betting_end_time, self.next_round_time) {
+            (Some(end), _) if now_ms >= end => Some(RoundAction::CloseBetting),
+            (Some(_), _) => None,
+            (None, Some(next)) if now_ms < next => None,
+            (None, _) => Some(RoundAction::StartRound),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundAction {
+    StartRound,
+    CloseBetting,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(rename_fields = "camelCase", input_name = "RoundConfigInput")]
+pub struct RoundConfig {
+    pub betting_duration_ms: u64,
+    pub cooldown_ms: u64,
+    pub auto_advance: bool,
+}
+
+impl Default for RoundConfig {
+    fn default() -> Self {
+        Self { betting_duration_ms: 30_000, cooldown_ms: 5_000, auto_advance: false }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum WheelVariant {
+    #[default]
+    SingleZero,
+    DoubleZero,
+}
+
+impl WheelVariant {
+    /// Number of pockets on the wheel: 0-36, plus 00 on double-zero wheels.
+    pub fn pocket_count(&self) -> u8 {
+        match self {
+            WheelVariant::SingleZero => 37,
+            WheelVariant::DoubleZero => 38,
+        }
+    }
+
+    pub fn is_valid_pocket(&self, number: u8) -> bool {
+        number < self.pocket_count()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(rename_fields = "camelCase", input_name = "TableConfigInput")]
+pub struct TableConfig {
+    pub name: String,
+    pub variant: WheelVariant,
+    pub min_bet: u64,
+    pub max_bet: Option<u64>,
+    pub round: RoundConfig,
+    #[serde(default)]
+    #[graphql(default)]
+    pub access: AccessPolicy,
+    #[serde(default)]
+    #[graphql(default)]
+    pub leave_policy: LeavePolicy,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        Self {
+            name: "Main Table".to_string(),
+            variant: WheelVariant::SingleZero,
+            min_bet: 1,
+            max_bet: None,
+            round: RoundConfig::default(),
+            access: AccessPolicy::default(),
+            leave_policy: LeavePolicy::default(),
+        }
+    }
+}
+
+/// What happens to a player's open bets when they leave or are kicked mid-round.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum LeavePolicy {
+    #[default]
+    Refund,
+    Forfeit,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum AccessKind {
+    #[default]
+    Open,
+    Allowlist,
+    InviteCode,
+}
+
+/// Who may sit at a table. Listed members are always admitted, whatever the kind.
+/// Only the kind leaves the host: members and the hash are never output or published.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(rename_fields = "camelCase", input_name = "AccessPolicyInput")]
+pub struct AccessPolicy {
+    pub kind: AccessKind,
+    #[graphql(default, skip_output)]
+    pub members: Vec<String>,
+    /// Hex SHA-256 of the invite code, salted with the host chain and table; see [`hash_invite_code`].
+    #[graphql(default, skip_output)]
+    pub invite_code_hash: Option<String>,
+}
+
+impl AccessPolicy {
+    /// Checks a seat request at table `table_id` of `host_chain_id`.
+    pub fn admits(&self, chain_id: &str, invite_code: Option<&str>, host_chain_id: &str, table_id: TableId) -> Result<(), String> {
+        if self.members.iter().any(|member| member == chain_id) {
+            return Ok(());
+        }
+        match self.kind {
+            AccessKind::Open => Ok(()),
+            AccessKind::Allowlist => Err("Not on the table allowlist".to_string()),
+            AccessKind::InviteCode => match (invite_code, &self.invite_code_hash) {
+                (None, _) => Err("Invite code required".to_string()),
+                (Some(code), Some(hash)) if hash_invite_code(host_chain_id, table_id, code) == *hash => Ok(()),
+                (Some(_), _) => Err("Invalid invite code".to_string()),
+            },
+        }
+    }
+
+    /// The policy as others may see it: its kind only.
+    pub fn public(&self) -> AccessPolicy {
+        AccessPolicy { kind: self.kind, ..AccessPolicy::default() }
+    }
+
+    pub fn add_member(&mut self, chain_id: String) {
+        if !self.members.contains(&chain_id) {
+            self.members.push(chain_id);
+        }
+    }
+
+    pub fn remove_member(&mut self, chain_id: &str) {
+        self.members.retain(|member| member != chain_id);
+    }
+}
+
+/// Salting with the host chain and table means a hash seen elsewhere cannot be
+/// matched against a table other than the one it was made for.
+pub fn hash_invite_code(host_chain_id: &str, table_id: TableId, code: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(host_chain_id.as_bytes());
+    hasher.update(table_id.to_le_bytes());
+    hasher.update(code.as_bytes());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl TableConfig {
+    /// The configuration as published in events and table listings.
+    pub fn public(&self) -> TableConfig {
+        TableConfig { access: self.access.public(), ..self.clone() }
+    }
+
+    /// Checks a bet against the table limits and wheel layout.
+    pub fn check_bet(&self, bet_type: BetType, numbers: &[u8], amount: u64) -> Result<(), BetError> {
+        if amount < self.min_bet {
+            return Err(BetError::BelowMinimum(self.min_bet));
+        }
+        if let Some(max_bet) = self.max_bet {
+            if amount > max_bet {
+                return Err(BetError::AboveMaximum(max_bet));
+            }
+        }
+        if bet_type == BetType::Straight
+            && (numbers.is_empty() || numbers.iter().any(|n| !self.variant.is_valid_pocket(*n)))
+        {
+            return Err(BetError::InvalidNumbers);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BetError {
+    #[error("Not registered")]
+    NotRegistered,
+    #[error("Not seated at this table")]
+    WrongTable,
+    #[error("Insufficient balance")]
+    InsufficientBalance,
+    #[error("Unknown table")]
+    UnknownTable,
+    #[error("Invalid amount")]
+    InvalidAmount,
+    #[error("Bet below table minimum of {0}")]
+    BelowMinimum(u64),
+    #[error("Bet above table maximum of {0}")]
+    AboveMaximum(u64),
+    #[error("Invalid numbers for this wheel")]
+    InvalidNumbers,
+    #[error("Wheel spinning")]
+    WheelSpinning,
+    #[error("Self-excluded until {0}")]
+    SelfExcluded(u64),
+    #[error("Cooling off until {0}")]
+    CoolingOff(u64),
+    #[error("Bet would exceed daily loss limit of {0}")]
+    DailyLossLimit(u64),
+    #[error("Bet would exceed weekly loss limit of {0}")]
+    WeeklyLossLimit(u64),
+    #[error("Bet would exceed daily wager limit of {0}")]
+    DailyWagerLimit(u64),
+    #[error("Bet would exceed weekly wager limit of {0}")]
+    WeeklyWagerLimit(u64),
+}
+
+impl BetError {
+    /// Stable identifier for clients, independent of the message wording.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BetError::NotRegistered => "NOT_REGISTERED",
+            BetError::WrongTable => "WRONG_TABLE",
+            BetError::InsufficientBalance => "INSUFFICIENT_BALANCE",
+            BetError::UnknownTable => "UNKNOWN_TABLE",
+            BetError::InvalidAmount => "INVALID_AMOUNT",
+            BetError::BelowMinimum(_) => "BELOW_MINIMUM",
+            BetError::AboveMaximum(_) => "ABOVE_MAXIMUM",
+            BetError::InvalidNumbers => "INVALID_NUMBERS",
+            BetError::WheelSpinning => "WHEEL_SPINNING",
+            BetError::SelfExcluded(_) => "SELF_EXCLUDED",
+            BetError::CoolingOff(_) => "COOLING_OFF",
+            BetError::DailyLossLimit(_) => "DAILY_LOSS_LIMIT",
+            BetError::WeeklyLossLimit(_) => "WEEKLY_LOSS_LIMIT",
+            BetError::DailyWagerLimit(_) => "DAILY_WAGER_LIMIT",
+            BetError::WeeklyWagerLimit(_) => "WEEKLY_WAGER_LIMIT",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NonceError {
+    #[error("Duplicate request {0}")]
+    Duplicate(RequestId),
+    #[error("Request {nonce} arrived after request {last}")]
+    OutOfOrder { nonce: RequestId, last: RequestId },
+}
+
+impl NonceError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            NonceError::Duplicate(_) => "DUPLICATE_REQUEST",
+            NonceError::OutOfOrder { .. } => "OUT_OF_ORDER_REQUEST",
+        }
+    }
+}
+
+/// Accepts `nonce` if it is newer than the last one processed for the sender.
+/// Gaps are fine: a player chain's requests may have gone to another host.
+pub fn check_nonce(last: Option<RequestId>, nonce: RequestId) -> Result<(), NonceError> {
+    match last {
+        Some(last) if nonce == last => Err(NonceError::Duplicate(nonce)),
+        Some(last) if nonce < last => Err(NonceError::OutOfOrder { nonce, last }),
+        _ => Ok(()),
+    }
+}
+
+/// Applies the host's bet rules and returns the player's balance once the bet is taken.
+pub fn validate_bet(
+    player: Option<&Player>,
+    table: Option<(&GameState, &TableConfig)>,
+    table_id: TableId,
+    bet_type: BetType,
+    numbers: &[u8],
+    amount: u64,
+) -> Result<u64, BetError> {
+    let player = player.ok_or(BetError::NotRegistered)?;
+    if player.table_id != table_id {
+        return Err(BetError::WrongTable);
+    }
+    if player.balance < amount {
+        return Err(BetError::InsufficientBalance);
+    }
+    let (game, config) = table.ok_or(BetError::UnknownTable)?;
+    config.check_bet(bet_type, numbers, amount)?;
+    if game.is_spinning {
+        return Err(BetError::WheelSpinning);
+    }
+    Ok(player.balance - amount)
+}
+
+// Stored layouts of schema version 1, kept only to read state written before the
+// multi-table upgrade. BCS has no field defaults, so each one needs an explicit upgrade.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerV1 {
+    pub chain_id: String,
+    pub name: String,
+    pub balance: u64,
+}
+
+impl PlayerV1 {
+    pub fn upgrade(self) -> Player {
+        Player { chain_id: self.chain_id, name: self.name, balance: self.balance, table_id: DEFAULT_TABLE_ID }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BetV1 {
+    pub player_chain_id: String,
+    pub player_name: String,
+    pub bet_type: BetType,
+    pub numbers: Vec<u8>,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameStateV1 {
+    pub is_spinning: bool,
+    pub current_bets: Vec<BetV1>,
+    pub last_result: Option<SpinResult>,
+    pub history: Vec<u8>,
+    pub betting_end_time: Option<u64>,
+}
+
+impl GameStateV1 {
+    /// The single table of a version 1 chain becomes `DEFAULT_TABLE_ID`.
+    pub fn upgrade(self) -> GameState {
+        let current_bets = self.current_bets.into_iter().map(|bet| Bet {
+            table_id: DEFAULT_TABLE_ID,
+            player_chain_id: bet.player_chain_id,
+            player_name: bet.player_name,
+            bet_type: bet.bet_type,
+            numbers: bet.numbers,
+            amount: bet.amount,
+        }).collect();
+        GameState {
+            is_spinning: self.is_spinning,
+            current_bets,
+            last_result: self.last_result,
+            history: self.history,
+            betting_end_time: self.betting_end_time,
+            next_round_time: None,
+        }
+    }
+}
+
+/// Lobby view of a table: its public configuration plus live occupancy.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct TableInfo {
+    pub table_id: TableId,
+    pub config: TableConfig,
+    pub player_count: u64,
+    pub total_pot: u64,
+    pub is_spinning: bool,
+    pub betting_end_time: Option<u64>,
+}
+
+impl TableInfo {
+    pub fn new(table_id: TableId, config: TableConfig, game: &GameState, player_count: u64) -> Self {
+        Self {
+            table_id,
+            config: config.public(),
+            player_count,
+            total_pot: game.current_bets.iter().map(|b| b.amount).sum(),
+            is_spinning: game.is_spinning,
+            betting_end_time: game.betting_end_time,
+        }
+    }
+}
+
+/// A pre-created host chain the registry hands out to whoever wants to run a table.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct Room {
+    pub chain_id: String,
+    pub name: String,
+    pub claimed_by: Option<String>,
+    pub last_heartbeat: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RoomError {
+    #[error("Unknown room")]
+    UnknownRoom,
+    #[error("Room already claimed")]
+    RoomTaken,
+    #[error("Room is not claimed by you")]
+    NotClaimant,
+}
+
+impl RoomError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            RoomError::UnknownRoom => "UNKNOWN_ROOM",
+            RoomError::RoomTaken => "ROOM_TAKEN",
+            RoomError::NotClaimant => "NOT_CLAIMANT",
+        }
+    }
+}
+
+/// A lease change a chain asks the registry for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoomAction {
+    Claim,
+    Release,
+    Heartbeat,
+}
+
+impl Room {
+    pub fn new(chain_id: String, name: String) -> Self {
+        Self { chain_id, name, claimed_by: None, last_heartbeat: 0 }
+    }
+
+    pub fn apply(&mut self, action: RoomAction, claimant: &str, now_ms: u64) -> Result<(), RoomError> {
+        match action {
+            RoomAction::Claim => self.claim(claimant, now_ms),
+            RoomAction::Release => self.release(claimant, now_ms),
+            RoomAction::Heartbeat => self.heartbeat(claimant, now_ms),
+        }
+    }
+
+    /// Free rooms and rooms whose claimant stopped sending heartbeats can be claimed.
+    pub fn is_available(&self, now_ms: u64) -> bool {
+        self.claimed_by.is_none() || now_ms.saturating_sub(self.last_heartbeat) >= ROOM_LEASE_MS
+    }
+
+    /// Claiming a room you already hold just renews the lease.
+    pub fn claim(&mut self, claimant: &str, now_ms: u64) -> Result<(), RoomError> {
+        if !self.is_available(now_ms) && self.claimed_by.as_deref() != Some(claimant) {
+            return Err(RoomError::RoomTaken);
+        }
+        self.claimed_by = Some(claimant.to_string());
+        self.last_heartbeat = now_ms;
+        Ok(())
+    }
+
+    pub fn heartbeat(&mut self, claimant: &str, now_ms: u64) -> Result<(), RoomError> {
+        self.check_claimant(claimant, now_ms)?;
+        self.last_heartbeat = now_ms;
+        Ok(())
+    }
+
+    pub fn release(&mut self, claimant: &str, now_ms: u64) -> Result<(), RoomError> {
+        self.check_claimant(claimant, now_ms)?;
+        self.claimed_by = None;
+        Ok(())
+    }
+
+    /// An expired lease no longer belongs to anyone, its former claimant included.
+    fn check_claimant(&self, claimant: &str, now_ms: u64) -> Result<(), RoomError> {
+        if self.is_available(now_ms) || self.claimed_by.as_deref() != Some(claimant) {
+            return Err(RoomError::NotClaimant);
+        }
+        Ok(())
+    }
+}
+
+/// A host chain's tables as last announced to a registry chain.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct HostListing {
+    pub host_chain_id: String,
+    pub tables: Vec<TableInfo>,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct ListedTable {
+    pub host_chain_id: String,
+    pub table: TableInfo,
+}
+
+/// A table switch waiting for the old host to release the player's chips.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct PendingJoin {
+    pub host_chain_id: String,
+    pub table_id: TableId,
+    #[graphql(skip)]
+    pub invite_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum RequestKind {
+    Register,
+    PlaceBet,
+    Limits,
+    Leave,
+}
+
+/// A request a player chain sent to its host and has not seen answered yet.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct PendingRequest {
+    pub request_id: RequestId,
+    pub kind: RequestKind,
+    pub table_id: TableId,
+    pub amount: u64,
+    pub sent_at: u64,
+}
+
+impl PendingRequest {
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms.saturating_sub(self.sent_at) >= REQUEST_TIMEOUT_MS
+    }
+}
+
+/// A tracked request that was rejected, bounced or timed out.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct RequestError {
+    pub request_id: RequestId,
+    pub kind: RequestKind,
+    pub error_message: String,
+    pub timestamp: u64,
+}
+
+/// Appends `error`, keeping only the most recent `LAST_ERRORS_LEN` entries.
+pub fn push_request_error(errors: &mut Vec<RequestError>, error: RequestError) {
+    errors.push(error);
+    if errors.len() > LAST_ERRORS_LEN {
+        errors.drain(..errors.len() - LAST_ERRORS_LEN);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum TableMode {
+    /// A host chain serving players on their own chains.
+    #[default]
+    Multiplayer,
+    /// One chain is both the house and its only player: no messages, and
+    /// every bet spins the wheel at once.
+    Solo,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InstantiationArgument {
     pub host_chain_id: Option<String>,
+    #[serde(default)]
+    pub table_config: TableConfig,
+    #[serde(default)]
+    pub registry_chain_id: Option<String>,
+    #[serde(default)]
+    pub registry: bool,
+    /// Follows the tables of `host_chain_id`, or of the host picked later with
+    /// `Operation::Spectate`, without ever taking a seat.
+    #[serde(default)]
+    pub spectator: bool,
+    /// `Solo` ignores `host_chain_id` and the registry.
+    #[serde(default)]
+    pub mode: TableMode,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Operation {
-    RegisterPlayer { player_id: String, name: String, initial_balance: u64 },
-    PlaceBet { player_id: String, bet_type: BetType, numbers: Vec<u8>, amount: u64 },
-    StartRound,
-    SpinWheel,
-    SettleBets { result: u8 },
+    RegisterPlayer { table_id: TableId, player_id: String, name: String, initial_balance: u64, invite_code: Option<String> },
+    PlaceBet { table_id: TableId, player_id: String, bet_type: BetType, numbers: Vec<u8>, amount: u64 },
+    StartRound { table_id: TableId },
+    SpinWheel { table_id: TableId },
+    SettleBets { table_id: TableId, result: u8 },
+    CreateTable { config: TableConfig },
+    ConfigureTable { table_id: TableId, config: TableConfig },
+    Tick,
+    AnnounceTables,
+    JoinTable { host_chain_id: String, table_id: TableId, invite_code: Option<String> },
+    AddTableMember { table_id: TableId, chain_id: String },
+    RemoveTableMember { table_id: TableId, chain_id: String },
+    LeaveTable,
+    KickPlayer { chain_id: String },
+    Spectate { host_chain_id: String },
+    SyncBalance,
+    AddRoom { chain_id: String, name: String },
+    /// Run on the claimant's own chain, which asks the registry for the room; the registry
+    /// takes the claimant from the message origin.
+    ClaimRoom { registry_chain_id: String, chain_id: String },
+    ReleaseRoom { registry_chain_id: String, chain_id: String },
+    HeartbeatRoom { registry_chain_id: String, chain_id: String },
+    /// Starts a fresh session at the table; `notify_players` pays balances out to player chains
+    /// instead of archiving them on the host.
+    ResetTable { table_id: TableId, notify_players: bool },
+    /// Bets `bets` on the player's table every round until a limit is reached.
+    StartAutoplay { bets: Vec<BetLayout>, rounds: u32, stop_loss: Option<u64>, take_profit: Option<u64> },
+    StopAutoplay,
+    /// Lets a built-in strategy choose the player's bets every round, starting from `bet`.
+    StartStrategy { kind: strategy::StrategyKind, bet: BetLayout },
+    StopStrategy,
+    /// Changes this player's responsible-gaming settings on the host.
+    ChangeLimits { change: limits::LimitChange },
+    /// Turns a chain that has no other role yet into a room registry.
+    EnableRegistry,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RouletteEvent {
+    /// Table events carry the [`TableConfig::public`] view of the config.
+    TableCreated { table_id: TableId, config: TableConfig, timestamp: String },
     PlayerRegistered { player: Player, timestamp: String },
+    RoundStarted { table_id: TableId, betting_end_time: u64, timestamp: String },
     BetPlaced { bet: Bet, timestamp: String },
-    WheelSpun { result: SpinResult, timestamp: String },
-    BetsSettled { timestamp: String },
+    BetRefunded { bet: Bet, timestamp: String },
+    WheelSpun { table_id: TableId, result: SpinResult, timestamp: String },
+    BetsSettled { table_id: TableId, timestamp: String },
+    /// The player's open bets are off the table: `refunded` to them or `forfeited` to the house.
+    PlayerLeft { player: Player, refunded: u64, forfeited: u64, kicked: bool, timestamp: String },
+    TableConfigured { table_id: TableId, config: TableConfig, timestamp: String },
+    /// Every seat at the table was cleared and a new session began. Open bets went back to
+    /// their players; balances were then either `paid_out` to player chains or `archived`.
+    TableReset { table_id: TableId, session_id: u64, refunded: u64, paid_out: u64, archived: u64, timestamp: String },
+    /// Betting closed on an empty table, so the wheel stays still until `next_round_time`.
+    RoundSkipped { table_id: TableId, next_round_time: u64, timestamp: String },
+}
+
+impl RouletteEvent {
+    pub fn table_id(&self) -> TableId {
+        match self {
+            RouletteEvent::TableCreated { table_id, .. }
+            | RouletteEvent::TableConfigured { table_id, .. }
+            | RouletteEvent::RoundStarted { table_id, .. }
+            | RouletteEvent::WheelSpun { table_id, .. }
+            | RouletteEvent::BetsSettled { table_id, .. }
+            | RouletteEvent::TableReset { table_id, .. }
+            | RouletteEvent::RoundSkipped { table_id, .. } => *table_id,
+            RouletteEvent::PlayerRegistered { player, .. } | RouletteEvent::PlayerLeft { player, .. } => player.table_id,
+            RouletteEvent::BetPlaced { bet, .. } | RouletteEvent::BetRefunded { bet, .. } => bet.table_id,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
-    RegisterPlayerRequest { name: String, initial_balance: u64 },
-    PlayerRegisteredConfirm { player: Player, success: bool, error_message: Option<String> },
-    PlaceBetRequest { bet_type: BetType, numbers: Vec<u8>, amount: u64, player_name: String },
-    BetPlacedConfirm { bet: Bet, success: bool, new_balance: u64, error_message: Option<String> },
-    SpinResultBroadcast { result: SpinResult, player_payout: u64, new_balance: u64, won: bool },
+    RegisterPlayerRequest { request_id: RequestId, table_id: TableId, name: String, initial_balance: u64, invite_code: Option<String> },
+    PlayerRegisteredConfirm { request_id: RequestId, player: Player, success: bool, error_message: Option<String> },
+    PlaceBetRequest { request_id: RequestId, table_id: TableId, bet_type: BetType, numbers: Vec<u8>, amount: u64, player_name: String },
+    BetPlacedConfirm { request_id: RequestId, bet: Bet, success: bool, new_balance: u64, error_message: Option<String> },
+    /// Private per-player outcome; the public result travels on the event stream.
+    SpinResultBroadcast { table_id: TableId, number: u8, player_payout: u64, new_balance: u64, won: bool },
     BalanceUpdate { new_balance: u64, reason: String },
-    SpinWheelRequest { player_chain_id: String },
+    SpinWheelRequest { request_id: RequestId, table_id: TableId, player_chain_id: String },
+    LeaveTableRequest { request_id: RequestId },
+    /// `request_id` is `None` when the host removed the player on its own; otherwise the player
+    /// chain only acts on it while that leave request is still pending.
+    LeaveTableConfirm { request_id: Option<RequestId>, player: Player, success: bool, kicked: bool, error_message: Option<String> },
+    AnnounceTables { tables: Vec<TableInfo> },
+    BalanceSnapshotRequest { request_id: RequestId },
+    /// The host's record of the player; `None` if the player is not seated there. `request_id`
+    /// is 0 when the host sends one unasked, in place of balance messages that bounced.
+    BalanceSnapshot { request_id: RequestId, player: Option<Player> },
+    LimitsRequest { request_id: RequestId, change: limits::LimitChange },
+    /// The host's record after the change; `error_message` is set if the change was refused.
+    LimitsConfirm { request_id: RequestId, settings: limits::ResponsibleGaming, error_message: Option<String> },
+    /// A lease change on a registry room, for the sending chain.
+    RoomRequest { chain_id: String, action: RoomAction },
+}
+
+/// What a chain does when one of its own tracked messages bounces back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BounceAction {
+    /// Player chain: the host never saw the request, so it failed.
+    FailRequest(RequestId),
+    /// Player chain: the host never saw the leave, so it failed and any table switch is off.
+    CancelLeave(RequestId),
+    /// Host: the player never learned about the seat; release it.
+    ReleaseSeat(String),
+    /// Host: the player never learned about the bet; take it back and refund it.
+    RefundBet(Bet),
+    /// Host: the message carries chips or results the player is owed; send what is owed again,
+    /// from current state.
+    Retry,
+    /// Nothing to compensate.
+    Ignore,
+}
+
+impl Message {
+    /// The nonce of a player-to-host request, `None` for every other message.
+    pub fn request_id(&self) -> Option<RequestId> {
+        match self {
+            Message::RegisterPlayerRequest { request_id, .. }
+            | Message::PlaceBetRequest { request_id, .. }
+            | Message::SpinWheelRequest { request_id, .. }
+            | Message::LeaveTableRequest { request_id }
+            | Message::BalanceSnapshotRequest { request_id }
+            | Message::LimitsRequest { request_id, .. } => Some(*request_id),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a host-to-player message, which a player chain only takes from its own host.
+    pub fn is_host_reply(&self) -> bool {
+        matches!(
+            self,
+            Message::PlayerRegisteredConfirm { .. }
+                | Message::BetPlacedConfirm { .. }
+                | Message::SpinResultBroadcast { .. }
+                | Message::BalanceUpdate { .. }
+                | Message::LeaveTableConfirm { .. }
+                | Message::BalanceSnapshot { .. }
+                | Message::LimitsConfirm { .. }
+        )
+    }
+}
+
+pub fn bounce_action(message: &Message) -> BounceAction {
+    match message {
+        Message::RegisterPlayerRequest { request_id, .. }
+        | Message::PlaceBetRequest { request_id, .. }
+        | Message::LimitsRequest { request_id, .. } => BounceAction::FailRequest(*request_id),
+        Message::LeaveTableRequest { request_id } => BounceAction::CancelLeave(*request_id),
+        Message::PlayerRegisteredConfirm { player, success: true, .. } => BounceAction::ReleaseSeat(player.chain_id.clone()),
+        Message::BetPlacedConfirm { bet, success: true, .. } => BounceAction::RefundBet(bet.clone()),
+        Message::SpinResultBroadcast { .. } | Message::BalanceUpdate { .. } => BounceAction::Retry,
+        Message::LeaveTableConfirm { success: true, .. } => BounceAction::Retry,
+        Message::LimitsConfirm { error_message: None, .. } => BounceAction::Retry,
+        Message::PlayerRegisteredConfirm { .. }
+        | Message::BetPlacedConfirm { .. }
+        | Message::LeaveTableConfirm { .. }
+        | Message::SpinWheelRequest { .. }
+        | Message::AnnounceTables { .. }
+        | Message::BalanceSnapshotRequest { .. }
+        | Message::BalanceSnapshot { .. }
+        | Message::LimitsConfirm { .. }
+        | Message::RoomRequest { .. } => BounceAction::Ignore,
+    }
+}
+
+/// Bounced messages waiting to be resent to one player chain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Undelivered {
+    pub messages: Vec<Message>,
+    pub failures: u32,
+}
+
+impl Undelivered {
+    /// Queues a bounced message; returns false, dropping the queue, once the
+    /// chain has bounced more than `MAX_DELIVERY_ATTEMPTS` times.
+    pub fn push(&mut self, message: Message) -> bool {
+        self.failures += 1;
+        if self.failures > MAX_DELIVERY_ATTEMPTS {
+            self.messages.clear();
+            return false;
+        }
+        self.messages.push(message);
+        true
+    }
 }
 
 impl BetType {
+    pub const ALL: [BetType; 13] = [
+        BetType::Straight,
+        BetType::Red,
+        BetType::Black,
+        BetType::Even,
+        BetType::Odd,
+        BetType::Low,
+        BetType::High,
+        BetType::FirstDozen,
+        BetType::SecondDozen,
+        BetType::ThirdDozen,
+        BetType::FirstColumn,
+        BetType::SecondColumn,
+        BetType::ThirdColumn,
+    ];
+
     pub fn payout_multiplier(&self) -> u64 {
         match self {
             BetType::Straight => 35,
@@ -136,35 +970,276 @@ impl BetType {
         }
     }
 
+    /// Amount returned on a winning bet, stake included.
+    pub fn payout(&self, amount: u64) -> u64 {
+        amount + amount * self.payout_multiplier()
+    }
+
+    /// `payout`, or `None` if it does not fit in a `u64`.
+    pub fn checked_payout(&self, amount: u64) -> Option<u64> {
+        amount.checked_mul(self.payout_multiplier())?.checked_add(amount)
+    }
+
     pub fn is_winner(&self, number: u8, bet_numbers: &[u8]) -> bool {
         match self {
             BetType::Straight => bet_numbers.contains(&number),
             BetType::Red => is_red(number),
             BetType::Black => is_black(number),
-            BetType::Even => number != 0 && number % 2 == 0,
-            BetType::Odd => number != 0 && number % 2 == 1,
+            BetType::Even => !is_zero(number) && number % 2 == 0,
+            BetType::Odd => !is_zero(number) && number % 2 == 1,
             BetType::Low => (1..=18).contains(&number),
             BetType::High => (19..=36).contains(&number),
             BetType::FirstDozen => (1..=12).contains(&number),
             BetType::SecondDozen => (13..=24).contains(&number),
             BetType::ThirdDozen => (25..=36).contains(&number),
-            BetType::FirstColumn => number != 0 && (number - 1) % 3 == 0,
-            BetType::SecondColumn => number != 0 && (number - 2) % 3 == 0,
-            BetType::ThirdColumn => number != 0 && number % 3 == 0,
+            BetType::FirstColumn => !is_zero(number) && number % 3 == 1,
+            BetType::SecondColumn => !is_zero(number) && number % 3 == 2,
+            BetType::ThirdColumn => !is_zero(number) && number % 3 == 0,
         }
     }
 }
 
-pub fn is_red(number: u8) -> bool {
-    matches!(number, 1 | 3 | 5 | 7 | 9 | 12 | 14 | 16 | 18 | 19 | 21 | 23 | 25 | 27 | 30 | 32 | 34 | 36)
-}
-
-pub fn is_black(number: u8) -> bool {
-    number != 0 && !is_red(number)
+/// A hypothetical bet, as submitted to `previewOutcomes`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(rename_fields = "camelCase", input_name = "BetInput")]
+pub struct BetLayout {
+    pub bet_type: BetType,
+    pub numbers: Vec<u8>,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct PocketOutcome {
+    pub number: u8,
+    pub color: String,
+    /// Total payout minus total stake if the ball lands here.
+    pub net: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct OutcomePreview {
+    pub variant: WheelVariant,
+    pub total_staked: u64,
+    pub pockets: Vec<PocketOutcome>,
+    pub expected_value: f64,
+    pub probability_of_profit: f64,
+    pub worst_case_loss: u64,
+}
+
+/// Net result of a bet layout for every pocket of the wheel, assuming each
+/// pocket is equally likely. Fails with `InvalidAmount` if a stake or payout
+/// is too large to count.
+pub fn preview_outcomes(bets: &[BetLayout], variant: WheelVariant) -> Result<OutcomePreview, BetError> {
+    let total_staked = bets.iter().try_fold(0u64, |sum, b| sum.checked_add(b.amount)).ok_or(BetError::InvalidAmount)?;
+    let staked = i64::try_from(total_staked).map_err(|_| BetError::InvalidAmount)?;
+    let pockets = (0..variant.pocket_count())
+        .map(|number| {
+            let returned = bets
+                .iter()
+                .filter(|b| b.bet_type.is_winner(number, &b.numbers))
+                .try_fold(0u64, |sum, b| sum.checked_add(b.bet_type.checked_payout(b.amount)?))
+                .and_then(|returned| i64::try_from(returned).ok())
+                .ok_or(BetError::InvalidAmount)?;
+            Ok(PocketOutcome { number, color: get_color(number), net: returned - staked })
+        })
+        .collect::<Result<Vec<_>, BetError>>()?;
+    let count = pockets.len() as f64;
+    let expected_value = pockets.iter().map(|p| p.net as f64).sum::<f64>() / count;
+    let probability_of_profit = pockets.iter().filter(|p| p.net > 0).count() as f64 / count;
+    let worst_case_loss = pockets.iter().map(|p| p.net).min().unwrap_or(0).min(0).unsigned_abs();
+    Ok(OutcomePreview { variant, total_staked, pockets, expected_value, probability_of_profit, worst_case_loss })
+}
+
+/// How one bet of the round was resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettledBet {
+    pub bet: Bet,
+    pub won: bool,
+    /// Amount credited to the player, stake included; zero for a losing bet or an unseated player.
+    pub payout: u64,
+}
+
+/// What a seated player who bet this round is told about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerPayout {
+    pub payout: u64,
+    pub new_balance: u64,
+    pub won: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Settlement {
+    /// Every bet of the round, in the order it was placed.
+    pub bets: Vec<SettledBet>,
+    pub winners: Vec<Winner>,
+    /// Keyed by player chain; only players who are still seated.
+    pub payouts: BTreeMap<String, PlayerPayout>,
+    /// Stakes kept minus payouts made.
+    pub house_net: i128,
+}
+
+/// Pays out `bets` for `result`. Stakes were taken from `balances` when the bets
+/// were placed; bets of players no longer in `balances` are forfeited. A payout
+/// is capped at what the player's balance can still hold.
+pub fn settle_bets(bets: &[Bet], result: u8, balances: &BTreeMap<String, u64>) -> Settlement {
+    let mut settlement = Settlement::default();
+    let mut balances = balances.clone();
+    let mut house_net = 0i128;
+
+    for bet in bets {
+        let won = bet.bet_type.is_winner(result, &bet.numbers);
+        let mut payout = 0;
+        if let Some(balance) = balances.get_mut(&bet.player_chain_id) {
+            if won {
+                let headroom = u64::MAX - *balance;
+                payout = bet.bet_type.checked_payout(bet.amount).map_or(headroom, |payout| payout.min(headroom));
+                *balance += payout;
+                settlement.winners.push(Winner {
+                    player_chain_id: bet.player_chain_id.clone(),
+                    player_name: bet.player_name.clone(),
+                    bet_type: bet.bet_type,
+                    bet_amount: bet.amount,
+                    payout,
+                });
+            }
+            let entry = settlement.payouts.entry(bet.player_chain_id.clone()).or_insert(PlayerPayout { payout: 0, new_balance: 0, won: false });
+            entry.payout += payout;
+            entry.new_balance = *balance;
+            entry.won |= won;
+        }
+        house_net += bet.amount as i128 - payout as i128;
+        settlement.bets.push(SettledBet { bet: bet.clone(), won, payout });
+    }
+
+    settlement.house_net = house_net;
+    settlement
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum AutoplayStop {
+    RoundsCompleted,
+    StopLoss,
+    TakeProfit,
+    InsufficientBalance,
+    BetRejected,
+    /// The player left, was kicked, or the table was reset.
+    Unseated,
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AutoplayError {
+    #[error("Autoplay needs at least one bet")]
+    NoBets,
+    #[error("Autoplay needs at least one round")]
+    NoRounds,
+    #[error("Autoplay bets need a positive amount")]
+    ZeroAmount,
+    #[error("Autoplay stake is too large")]
+    StakeTooLarge,
+}
+
+impl AutoplayError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AutoplayError::NoBets => "NO_BETS",
+            AutoplayError::NoRounds => "NO_ROUNDS",
+            AutoplayError::ZeroAmount => "INVALID_AMOUNT",
+            AutoplayError::StakeTooLarge => "STAKE_TOO_LARGE",
+        }
+    }
+}
+
+/// A player chain's plan to bet the same layout for several rounds unattended.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct Autoplay {
+    pub table_id: TableId,
+    pub bets: Vec<BetLayout>,
+    pub rounds: u32,
+    pub rounds_played: u32,
+    /// Stop once the balance has fallen this far below `starting_balance`.
+    pub stop_loss: Option<u64>,
+    /// Stop once the balance has risen this far above `starting_balance`.
+    pub take_profit: Option<u64>,
+    pub starting_balance: u64,
+    /// Why autoplay ended; `None` while it is running.
+    pub stopped: Option<AutoplayStop>,
+}
+
+impl Autoplay {
+    pub fn new(
+        table_id: TableId,
+        bets: Vec<BetLayout>,
+        rounds: u32,
+        stop_loss: Option<u64>,
+        take_profit: Option<u64>,
+        starting_balance: u64,
+    ) -> Result<Self, AutoplayError> {
+        if bets.is_empty() { return Err(AutoplayError::NoBets); }
+        if rounds == 0 { return Err(AutoplayError::NoRounds); }
+        if bets.iter().any(|b| b.amount == 0) { return Err(AutoplayError::ZeroAmount); }
+        if bets.iter().try_fold(0u64, |sum, b| sum.checked_add(b.amount)).is_none() { return Err(AutoplayError::StakeTooLarge); }
+        Ok(Self { table_id, bets, rounds, rounds_played: 0, stop_loss, take_profit, starting_balance, stopped: None })
+    }
+
+    /// Chips staked each round. `new` checked that the total fits, so this never saturates.
+    pub fn stake(&self) -> u64 {
+        self.bets.iter().fold(0, |sum, b| sum.saturating_add(b.amount))
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.stopped.is_none()
+    }
+
+    pub fn stop(&mut self, reason: AutoplayStop) {
+        if self.is_active() { self.stopped = Some(reason); }
+    }
+
+    /// Whether another round may be placed at `balance`; stops the plan when a limit is reached.
+    pub fn can_continue(&mut self, balance: u64) -> bool {
+        if !self.is_active() { return false; }
+        let loss = self.starting_balance.saturating_sub(balance);
+        let profit = balance.saturating_sub(self.starting_balance);
+        let limit = if self.rounds_played >= self.rounds {
+            Some(AutoplayStop::RoundsCompleted)
+        } else if self.stop_loss.is_some_and(|limit| loss >= limit) {
+            Some(AutoplayStop::StopLoss)
+        } else if self.take_profit.is_some_and(|limit| profit >= limit) {
+            Some(AutoplayStop::TakeProfit)
+        } else if balance < self.stake() {
+            Some(AutoplayStop::InsufficientBalance)
+        } else {
+            None
+        };
+        if let Some(reason) = limit { self.stop(reason); }
+        limit.is_none()
+    }
+
+    /// Counts a settled round and reports whether the next one should be placed.
+    pub fn round_settled(&mut self, balance: u64) -> bool {
+        if !self.is_active() { return false; }
+        self.rounds_played += 1;
+        self.can_continue(balance)
+    }
+}
+
+pub fn is_red(number: u8) -> bool {
+    matches!(number, 1 | 3 | 5 | 7 | 9 | 12 | 14 | 16 | 18 | 19 | 21 | 23 | 25 | 27 | 30 | 32 | 34 | 36)
+}
+
+pub fn is_zero(number: u8) -> bool {
+    number == 0 || number == DOUBLE_ZERO
+}
+
+pub fn is_black(number: u8) -> bool {
+    (1..=36).contains(&number) && !is_red(number)
 }
 
 pub fn get_color(number: u8) -> String {
-    if number == 0 { "green".to_string() }
+    if is_zero(number) { "green".to_string() }
     else if is_red(number) { "red".to_string() }
     else { "black".to_string() }
 }
@@ -172,379 +1247,1176 @@ pub fn get_color(number: u8) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    // ==================== PAYOUT TESTS (10 tests) ====================
+
+    #[test]
+    fn test_straight_payout() {
+        assert_eq!(BetType::Straight.payout_multiplier(), 35);
+    }
+
+    #[test]
+    fn test_red_payout() {
+        assert_eq!(BetType::Red.payout_multiplier(), 1);
+    }
+
+    #[test]
+    fn test_black_payout() {
+        assert_eq!(BetType::Black.payout_multiplier(), 1);
+    }
+
+    #[test]
+    fn test_even_payout() {
+        assert_eq!(BetType::Even.payout_multiplier(), 1);
+    }
+
+    #[test]
+    fn test_odd_payout() {
+        assert_eq!(BetType::Odd.payout_multiplier(), 1);
+    }
+
+    #[test]
+    fn test_low_payout() {
+        assert_eq!(BetType::Low.payout_multiplier(), 1);
+    }
+
+    #[test]
+    fn test_high_payout() {
+        assert_eq!(BetType::High.payout_multiplier(), 1);
+    }
+
+    #[test]
+    fn test_dozen_payouts() {
+        assert_eq!(BetType::FirstDozen.payout_multiplier(), 2);
+        assert_eq!(BetType::SecondDozen.payout_multiplier(), 2);
+        assert_eq!(BetType::ThirdDozen.payout_multiplier(), 2);
+    }
+
+    #[test]
+    fn test_column_payouts() {
+        assert_eq!(BetType::FirstColumn.payout_multiplier(), 2);
+        assert_eq!(BetType::SecondColumn.payout_multiplier(), 2);
+        assert_eq!(BetType::ThirdColumn.payout_multiplier(), 2);
+    }
+
+    #[test]
+    fn test_payout_calculation() {
+        let bet_amount: u64 = 100;
+        assert_eq!(bet_amount + bet_amount * BetType::Straight.payout_multiplier(), 3600);
+        assert_eq!(bet_amount + bet_amount * BetType::Red.payout_multiplier(), 200);
+        assert_eq!(bet_amount + bet_amount * BetType::FirstDozen.payout_multiplier(), 300);
+    }
+
+    // ==================== COLOR TESTS (6 tests) ====================
+
+    #[test]
+    fn test_zero_is_green() {
+        assert_eq!(get_color(0), "green");
+        assert!(!is_red(0));
+        assert!(!is_black(0));
+    }
+
+    #[test]
+    fn test_red_numbers() {
+        let red_numbers = [1, 3, 5, 7, 9, 12, 14, 16, 18, 19, 21, 23, 25, 27, 30, 32, 34, 36];
+        for n in red_numbers {
+            assert!(is_red(n), "Number {} should be red", n);
+            assert!(!is_black(n), "Number {} should not be black", n);
+            assert_eq!(get_color(n), "red");
+        }
+    }
+
+    #[test]
+    fn test_black_numbers() {
+        let black_numbers = [2, 4, 6, 8, 10, 11, 13, 15, 17, 20, 22, 24, 26, 28, 29, 31, 33, 35];
+        for n in black_numbers {
+            assert!(is_black(n), "Number {} should be black", n);
+            assert!(!is_red(n), "Number {} should not be red", n);
+            assert_eq!(get_color(n), "black");
+        }
+    }
+
+    #[test]
+    fn test_all_numbers_have_color() {
+        for n in 0..=36 {
+            let color = get_color(n);
+            assert!(color == "green" || color == "red" || color == "black");
+        }
+    }
+
+    #[test]
+    fn test_color_count() {
+        let red_count = (1..=36).filter(|&n| is_red(n)).count();
+        let black_count = (1..=36).filter(|&n| is_black(n)).count();
+        assert_eq!(red_count, 18);
+        assert_eq!(black_count, 18);
+    }
+
+    #[test]
+    fn test_zero_not_red_not_black() {
+        assert!(!is_red(0));
+        assert!(!is_black(0));
+    }
+
+    // ==================== STRAIGHT BET TESTS (4 tests) ====================
+
+    #[test]
+    fn test_straight_bet_wins_exact() {
+        assert!(BetType::Straight.is_winner(17, &[17]));
+        assert!(BetType::Straight.is_winner(0, &[0]));
+        assert!(BetType::Straight.is_winner(36, &[36]));
+    }
+
+    #[test]
+    fn test_straight_bet_loses_different() {
+        assert!(!BetType::Straight.is_winner(17, &[18]));
+        assert!(!BetType::Straight.is_winner(0, &[1]));
+        assert!(!BetType::Straight.is_winner(5, &[10, 15, 20]));
+    }
+
+    #[test]
+    fn test_straight_bet_multiple_numbers() {
+        assert!(BetType::Straight.is_winner(5, &[1, 5, 10]));
+        assert!(!BetType::Straight.is_winner(7, &[1, 5, 10]));
+    }
+
+    #[test]
+    fn test_straight_bet_empty_numbers() {
+        assert!(!BetType::Straight.is_winner(17, &[]));
+    }
+
+    // ==================== RED/BLACK BET TESTS (4 tests) ====================
+
+    #[test]
+    fn test_red_bet_wins() {
+        assert!(BetType::Red.is_winner(1, &[]));
+        assert!(BetType::Red.is_winner(19, &[]));
+        assert!(BetType::Red.is_winner(36, &[]));
+    }
+
+    #[test]
+    fn test_red_bet_loses() {
+        assert!(!BetType::Red.is_winner(0, &[]));
+        assert!(!BetType::Red.is_winner(2, &[]));
+        assert!(!BetType::Red.is_winner(17, &[]));
+    }
+
+    #[test]
+    fn test_black_bet_wins() {
+        assert!(BetType::Black.is_winner(2, &[]));
+        assert!(BetType::Black.is_winner(17, &[]));
+        assert!(BetType::Black.is_winner(35, &[]));
+    }
+
+    #[test]
+    fn test_black_bet_loses() {
+        assert!(!BetType::Black.is_winner(0, &[]));
+        assert!(!BetType::Black.is_winner(1, &[]));
+        assert!(!BetType::Black.is_winner(36, &[]));
+    }
+
+    // ==================== EVEN/ODD BET TESTS (4 tests) ====================
+
+    #[test]
+    fn test_even_bet_wins() {
+        assert!(BetType::Even.is_winner(2, &[]));
+        assert!(BetType::Even.is_winner(18, &[]));
+        assert!(BetType::Even.is_winner(36, &[]));
+    }
+
+    #[test]
+    fn test_even_bet_loses_on_odd() {
+        assert!(!BetType::Even.is_winner(1, &[]));
+        assert!(!BetType::Even.is_winner(17, &[]));
+        assert!(!BetType::Even.is_winner(35, &[]));
+    }
+
+    #[test]
+    fn test_odd_bet_wins() {
+        assert!(BetType::Odd.is_winner(1, &[]));
+        assert!(BetType::Odd.is_winner(17, &[]));
+        assert!(BetType::Odd.is_winner(35, &[]));
+    }
+
+    #[test]
+    fn test_even_odd_zero_loses() {
+        assert!(!BetType::Even.is_winner(0, &[]));
+        assert!(!BetType::Odd.is_winner(0, &[]));
+    }
+
+    // ==================== LOW/HIGH BET TESTS (4 tests) ====================
+
+    #[test]
+    fn test_low_bet_wins() {
+        assert!(BetType::Low.is_winner(1, &[]));
+        assert!(BetType::Low.is_winner(10, &[]));
+        assert!(BetType::Low.is_winner(18, &[]));
+    }
+
+    #[test]
+    fn test_low_bet_loses() {
+        assert!(!BetType::Low.is_winner(0, &[]));
+        assert!(!BetType::Low.is_winner(19, &[]));
+        assert!(!BetType::Low.is_winner(36, &[]));
+    }
+
+    #[test]
+    fn test_high_bet_wins() {
+        assert!(BetType::High.is_winner(19, &[]));
+        assert!(BetType::High.is_winner(25, &[]));
+        assert!(BetType::High.is_winner(36, &[]));
+    }
+
+    #[test]
+    fn test_high_bet_loses() {
+        assert!(!BetType::High.is_winner(0, &[]));
+        assert!(!BetType::High.is_winner(1, &[]));
+        assert!(!BetType::High.is_winner(18, &[]));
+    }
+
+    // ==================== DOZEN BET TESTS (6 tests) ====================
+
+    #[test]
+    fn test_first_dozen_wins() {
+        for n in 1..=12 {
+            assert!(BetType::FirstDozen.is_winner(n, &[]), "FirstDozen should win on {}", n);
+        }
+    }
+
+    #[test]
+    fn test_first_dozen_loses() {
+        assert!(!BetType::FirstDozen.is_winner(0, &[]));
+        assert!(!BetType::FirstDozen.is_winner(13, &[]));
+        assert!(!BetType::FirstDozen.is_winner(36, &[]));
+    }
+
+    #[test]
+    fn test_second_dozen_wins() {
+        for n in 13..=24 {
+            assert!(BetType::SecondDozen.is_winner(n, &[]), "SecondDozen should win on {}", n);
+        }
+    }
+
+    #[test]
+    fn test_second_dozen_loses() {
+        assert!(!BetType::SecondDozen.is_winner(0, &[]));
+        assert!(!BetType::SecondDozen.is_winner(12, &[]));
+        assert!(!BetType::SecondDozen.is_winner(25, &[]));
+    }
+
+    #[test]
+    fn test_third_dozen_wins() {
+        for n in 25..=36 {
+            assert!(BetType::ThirdDozen.is_winner(n, &[]), "ThirdDozen should win on {}", n);
+        }
+    }
+
+    #[test]
+    fn test_third_dozen_loses() {
+        assert!(!BetType::ThirdDozen.is_winner(0, &[]));
+        assert!(!BetType::ThirdDozen.is_winner(24, &[]));
+    }
+
+    // ==================== COLUMN BET TESTS (6 tests) ====================
+
+    #[test]
+    fn test_first_column_wins() {
+        let first_col = [1, 4, 7, 10, 13, 16, 19, 22, 25, 28, 31, 34];
+        for n in first_col {
+            assert!(BetType::FirstColumn.is_winner(n, &[]), "FirstColumn should win on {}", n);
+        }
+    }
+
+    #[test]
+    fn test_first_column_loses() {
+        assert!(!BetType::FirstColumn.is_winner(0, &[]));
+        assert!(!BetType::FirstColumn.is_winner(2, &[]));
+        assert!(!BetType::FirstColumn.is_winner(3, &[]));
+    }
+
+    #[test]
+    fn test_second_column_wins() {
+        let second_col = [2, 5, 8, 11, 14, 17, 20, 23, 26, 29, 32, 35];
+        for n in second_col {
+            assert!(BetType::SecondColumn.is_winner(n, &[]), "SecondColumn should win on {}", n);
+        }
+    }
+
+    #[test]
+    fn test_second_column_loses() {
+        assert!(!BetType::SecondColumn.is_winner(0, &[]));
+        assert!(!BetType::SecondColumn.is_winner(3, &[]));
+        assert!(!BetType::SecondColumn.is_winner(4, &[]));
+    }
+
+    #[test]
+    fn test_third_column_wins() {
+        let third_col = [3, 6, 9, 12, 15, 18, 21, 24, 27, 30, 33, 36];
+        for n in third_col {
+            assert!(BetType::ThirdColumn.is_winner(n, &[]), "ThirdColumn should win on {}", n);
+        }
+    }
+
+    #[test]
+    fn test_third_column_loses() {
+        assert!(!BetType::ThirdColumn.is_winner(0, &[]));
+        assert!(!BetType::ThirdColumn.is_winner(1, &[]));
+        assert!(!BetType::ThirdColumn.is_winner(2, &[]));
+    }
+
+    // ==================== EDGE CASE TESTS (4 tests) ====================
+
+    #[test]
+    fn test_zero_loses_all_outside_bets() {
+        assert!(!BetType::Red.is_winner(0, &[]));
+        assert!(!BetType::Black.is_winner(0, &[]));
+        assert!(!BetType::Even.is_winner(0, &[]));
+        assert!(!BetType::Odd.is_winner(0, &[]));
+        assert!(!BetType::Low.is_winner(0, &[]));
+        assert!(!BetType::High.is_winner(0, &[]));
+        assert!(!BetType::FirstDozen.is_winner(0, &[]));
+        assert!(!BetType::SecondDozen.is_winner(0, &[]));
+        assert!(!BetType::ThirdDozen.is_winner(0, &[]));
+        assert!(!BetType::FirstColumn.is_winner(0, &[]));
+        assert!(!BetType::SecondColumn.is_winner(0, &[]));
+        assert!(!BetType::ThirdColumn.is_winner(0, &[]));
+    }
+
+    #[test]
+    fn test_zero_wins_straight_bet() {
+        assert!(BetType::Straight.is_winner(0, &[0]));
+    }
+
+    #[test]
+    fn test_boundary_numbers() {
+        assert!(BetType::Low.is_winner(1, &[]));
+        assert!(BetType::Low.is_winner(18, &[]));
+        assert!(!BetType::Low.is_winner(19, &[]));
+        assert!(BetType::High.is_winner(19, &[]));
+        assert!(BetType::High.is_winner(36, &[]));
+        assert!(!BetType::High.is_winner(18, &[]));
+    }
+
+    #[test]
+    fn test_all_numbers_valid_range() {
+        for n in 0..=36 {
+            let _ = get_color(n);
+            let _ = BetType::Straight.is_winner(n, &[n]);
+        }
+    }
+
+    // ==================== GAME STATE TESTS (2 tests) ====================
+
+    #[test]
+    fn test_game_state_default() {
+        let state = GameState::new();
+        assert!(!state.is_spinning);
+        assert!(state.current_bets.is_empty());
+        assert!(state.last_result.is_none());
+        assert!(state.history.is_empty());
+        assert!(state.betting_end_time.is_none());
+        assert!(state.next_round_time.is_none());
+    }
+
+    #[test]
+    fn test_instantiation_argument_default() {
+        let arg = InstantiationArgument::default();
+        assert!(arg.host_chain_id.is_none());
+        assert!(!arg.table_config.round.auto_advance);
+        assert!(arg.registry_chain_id.is_none());
+        assert!(!arg.registry);
+        assert!(!arg.spectator);
+        assert_eq!(arg.table_config.leave_policy, LeavePolicy::Refund);
+    }
+
+    // ==================== EVENT MIRROR TESTS (4 tests) ====================
+
+    fn test_bet(player: &str, amount: u64) -> Bet {
+        Bet {
+            table_id: 0,
+            player_chain_id: player.to_string(),
+            player_name: player.to_string(),
+            bet_type: BetType::Red,
+            numbers: vec![],
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_mirror_follows_round() {
+        let mut game = GameState::new();
+        let timestamp = String::new();
+        game.apply_event(&RouletteEvent::RoundStarted { table_id: 0, betting_end_time: 500, timestamp: timestamp.clone() });
+        game.apply_event(&RouletteEvent::BetPlaced { bet: test_bet("alice", 10), timestamp: timestamp.clone() });
+        assert_eq!(game.betting_end_time, Some(500));
+        assert_eq!(game.current_bets.len(), 1);
+
+        let result = SpinResult { number: 7, color: get_color(7), timestamp: timestamp.clone(), winners: vec![] };
+        game.apply_event(&RouletteEvent::WheelSpun { table_id: 0, result, timestamp });
+        assert!(game.current_bets.is_empty());
+        assert_eq!(game.history, vec![7]);
+        assert_eq!(game.last_result.map(|r| r.number), Some(7));
+        assert!(game.betting_end_time.is_none());
+    }
+
+    #[test]
+    fn test_mirror_follows_skipped_round() {
+        let mut game = GameState::new();
+        game.apply_event(&RouletteEvent::RoundStarted { table_id: 0, betting_end_time: 500, timestamp: String::new() });
+        game.apply_event(&RouletteEvent::RoundSkipped { table_id: 0, next_round_time: 800, timestamp: String::new() });
+        assert!(game.betting_end_time.is_none());
+        assert_eq!(game.next_round_time, Some(800));
+        assert!(game.history.is_empty());
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut game = GameState::new();
+        for number in 0..15 {
+            game.record_result(SpinResult { number, color: get_color(number), timestamp: String::new(), winners: vec![] });
+        }
+        assert_eq!(game.history.len(), HISTORY_LEN);
+        assert_eq!(game.history.first(), Some(&5));
+    }
+
+    #[test]
+    fn test_mirror_drops_refunded_bets() {
+        let mut game = GameState { current_bets: vec![test_bet("alice", 10), test_bet("bob", 5)], ..GameState::new() };
+        let player = Player { chain_id: "alice".to_string(), name: "alice".to_string(), balance: 10, table_id: 0 };
+        game.apply_event(&RouletteEvent::PlayerLeft { player, refunded: 10, forfeited: 0, kicked: false, timestamp: String::new() });
+        assert_eq!(game.current_bets.len(), 1);
+        assert_eq!(game.current_bets[0].player_chain_id, "bob");
+
+        // Forfeited bets leave the table too; the house keeps their stake.
+        let player = Player { chain_id: "bob".to_string(), name: "bob".to_string(), balance: 0, table_id: 0 };
+        game.apply_event(&RouletteEvent::PlayerLeft { player, refunded: 0, forfeited: 5, kicked: true, timestamp: String::new() });
+        assert!(game.current_bets.is_empty());
+    }
+
+    // ==================== ACCESS POLICY TESTS (6 tests) ====================
+
+    #[test]
+    fn test_open_table_admits_anyone() {
+        assert!(AccessPolicy::default().admits("chain", None, "host", 0).is_ok());
+    }
+
+    #[test]
+    fn test_allowlist_admits_members_only() {
+        let mut policy = AccessPolicy { kind: AccessKind::Allowlist, ..AccessPolicy::default() };
+        assert!(policy.admits("alice", None, "host", 0).is_err());
+        policy.add_member("alice".to_string());
+        policy.add_member("alice".to_string());
+        assert_eq!(policy.members.len(), 1);
+        assert!(policy.admits("alice", None, "host", 0).is_ok());
+        policy.remove_member("alice");
+        assert!(policy.admits("alice", None, "host", 0).is_err());
+    }
+
+    #[test]
+    fn test_invite_code_checks_hash() {
+        let policy = AccessPolicy {
+            kind: AccessKind::InviteCode,
+            members: vec![],
+            invite_code_hash: Some(hash_invite_code("host", 1, "lucky-7")),
+        };
+        assert!(policy.admits("bob", Some("lucky-7"), "host", 1).is_ok());
+        assert_eq!(policy.admits("bob", Some("lucky-8"), "host", 1), Err("Invalid invite code".to_string()));
+        assert_eq!(policy.admits("bob", None, "host", 1), Err("Invite code required".to_string()));
+        // The same code does not open another table or another host's table.
+        assert!(policy.admits("bob", Some("lucky-7"), "host", 2).is_err());
+        assert!(policy.admits("bob", Some("lucky-7"), "other", 1).is_err());
+    }
+
+    #[test]
+    fn test_invite_code_hash_is_salted_hex_sha256() {
+        assert_eq!(
+            hash_invite_code("host", 0, "abc"),
+            "b2a360e7e053bd780c0984d288bafb7ce859b82ab7e3f498ec6bb746da56ee9f"
+        );
+        assert_ne!(hash_invite_code("host", 0, "abc"), hash_invite_code("host", 1, "abc"));
+    }
+
+    #[test]
+    fn test_public_config_keeps_access_kind_only() {
+        let access = AccessPolicy {
+            kind: AccessKind::InviteCode,
+            members: vec!["alice".to_string()],
+            invite_code_hash: Some(hash_invite_code("host", 0, "lucky-7")),
+        };
+        let config = TableConfig { access, ..TableConfig::default() };
+        let info = TableInfo::new(0, config.clone(), &GameState::new(), 0);
+        for public in [config.public(), info.config] {
+            assert_eq!(public.access.kind, AccessKind::InviteCode);
+            assert!(public.access.members.is_empty());
+            assert!(public.access.invite_code_hash.is_none());
+        }
+        assert_eq!(config.access.members.len(), 1);
+    }
+
+    #[test]
+    fn test_table_info_sums_pot() {
+        let bet = |amount| Bet {
+            table_id: 1,
+            player_chain_id: "chain".to_string(),
+            player_name: "player".to_string(),
+            bet_type: BetType::Red,
+            numbers: vec![],
+            amount,
+        };
+        let game = GameState { current_bets: vec![bet(10), bet(25)], ..GameState::new() };
+        let info = TableInfo::new(1, TableConfig::default(), &game, 2);
+        assert_eq!(info.total_pot, 35);
+        assert_eq!(info.player_count, 2);
+    }
+
+    // ==================== DOUBLE ZERO TESTS (3 tests) ====================
+
+    #[test]
+    fn test_double_zero_is_green() {
+        assert_eq!(get_color(DOUBLE_ZERO), "green");
+        assert!(!is_red(DOUBLE_ZERO));
+        assert!(!is_black(DOUBLE_ZERO));
+    }
+
+    #[test]
+    fn test_double_zero_loses_all_outside_bets() {
+        let outside = [
+            BetType::Red, BetType::Black, BetType::Even, BetType::Odd, BetType::Low, BetType::High,
+            BetType::FirstDozen, BetType::SecondDozen, BetType::ThirdDozen,
+            BetType::FirstColumn, BetType::SecondColumn, BetType::ThirdColumn,
+        ];
+        for bet_type in outside {
+            assert!(!bet_type.is_winner(DOUBLE_ZERO, &[]), "{:?} should lose on 00", bet_type);
+        }
+        assert!(BetType::Straight.is_winner(DOUBLE_ZERO, &[DOUBLE_ZERO]));
+    }
+
+    #[test]
+    fn test_wheel_variant_pockets() {
+        assert_eq!(WheelVariant::SingleZero.pocket_count(), 37);
+        assert_eq!(WheelVariant::DoubleZero.pocket_count(), 38);
+        assert!(!WheelVariant::SingleZero.is_valid_pocket(DOUBLE_ZERO));
+        assert!(WheelVariant::DoubleZero.is_valid_pocket(DOUBLE_ZERO));
+    }
+
+    // ==================== TABLE LIMIT TESTS (5 tests) ====================
+
+    #[test]
+    fn test_table_limits() {
+        let config = TableConfig { min_bet: 10, max_bet: Some(500), ..TableConfig::default() };
+        assert!(config.check_bet(BetType::Red, &[], 10).is_ok());
+        assert!(config.check_bet(BetType::Red, &[], 500).is_ok());
+        assert!(config.check_bet(BetType::Red, &[], 9).is_err());
+        assert!(config.check_bet(BetType::Red, &[], 501).is_err());
+    }
+
+    #[test]
+    fn test_table_without_max_bet() {
+        let config = TableConfig::default();
+        assert!(config.check_bet(BetType::Black, &[], u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_validate_bet_order() {
+        let player = Player { chain_id: "alice".to_string(), name: "alice".to_string(), balance: 100, table_id: 0 };
+        let config = TableConfig { min_bet: 10, ..TableConfig::default() };
+        let game = GameState::new();
+        let table = Some((&game, &config));
+        assert_eq!(validate_bet(None, table, 0, BetType::Red, &[], 10), Err(BetError::NotRegistered));
+        assert_eq!(validate_bet(Some(&player), table, 1, BetType::Red, &[], 10), Err(BetError::WrongTable));
+        assert_eq!(validate_bet(Some(&player), table, 0, BetType::Red, &[], 101), Err(BetError::InsufficientBalance));
+        assert_eq!(validate_bet(Some(&player), None, 0, BetType::Red, &[], 10), Err(BetError::UnknownTable));
+        assert_eq!(validate_bet(Some(&player), table, 0, BetType::Red, &[], 5), Err(BetError::BelowMinimum(10)));
+        assert_eq!(validate_bet(Some(&player), table, 0, BetType::Red, &[], 40), Ok(60));
+
+        let spinning = GameState { is_spinning: true, ..GameState::new() };
+        assert_eq!(validate_bet(Some(&player), Some((&spinning, &config)), 0, BetType::Red, &[], 10), Err(BetError::WheelSpinning));
+    }
+
+    #[test]
+    fn test_bet_error_codes_and_messages() {
+        assert_eq!(BetError::InsufficientBalance.code(), "INSUFFICIENT_BALANCE");
+        assert_eq!(BetError::InsufficientBalance.to_string(), "Insufficient balance");
+        assert_eq!(BetError::AboveMaximum(500).to_string(), "Bet above table maximum of 500");
+    }
+
+    #[test]
+    fn test_straight_numbers_follow_variant() {
+        let single = TableConfig::default();
+        let double = TableConfig { variant: WheelVariant::DoubleZero, ..TableConfig::default() };
+        assert!(single.check_bet(BetType::Straight, &[DOUBLE_ZERO], 10).is_err());
+        assert!(double.check_bet(BetType::Straight, &[DOUBLE_ZERO], 10).is_ok());
+        assert!(single.check_bet(BetType::Straight, &[], 10).is_err());
+    }
 
-    // ==================== PAYOUT TESTS (10 tests) ====================
+    // ==================== ROUND SCHEDULER TESTS (5 tests) ====================
+
+    fn auto_config() -> RoundConfig {
+        RoundConfig { betting_duration_ms: 30_000, cooldown_ms: 5_000, auto_advance: true }
+    }
 
     #[test]
-    fn test_straight_payout() {
-        assert_eq!(BetType::Straight.payout_multiplier(), 35);
+    fn test_scheduler_disabled_without_auto_advance() {
+        let state = GameState { betting_end_time: Some(0), ..GameState::new() };
+        assert_eq!(state.due_action(1_000, &RoundConfig::default()), None);
     }
 
     #[test]
-    fn test_red_payout() {
-        assert_eq!(BetType::Red.payout_multiplier(), 1);
+    fn test_scheduler_starts_idle_table() {
+        assert_eq!(GameState::new().due_action(1_000, &auto_config()), Some(RoundAction::StartRound));
     }
 
     #[test]
-    fn test_black_payout() {
-        assert_eq!(BetType::Black.payout_multiplier(), 1);
+    fn test_scheduler_waits_for_betting_end() {
+        let state = GameState { betting_end_time: Some(10_000), ..GameState::new() };
+        assert_eq!(state.due_action(9_999, &auto_config()), None);
+        assert_eq!(state.due_action(10_000, &auto_config()), Some(RoundAction::CloseBetting));
     }
 
     #[test]
-    fn test_even_payout() {
-        assert_eq!(BetType::Even.payout_multiplier(), 1);
+    fn test_scheduler_respects_cooldown() {
+        let state = GameState { next_round_time: Some(20_000), ..GameState::new() };
+        assert_eq!(state.due_action(19_000, &auto_config()), None);
+        assert_eq!(state.due_action(20_000, &auto_config()), Some(RoundAction::StartRound));
     }
 
     #[test]
-    fn test_odd_payout() {
-        assert_eq!(BetType::Odd.payout_multiplier(), 1);
+    fn test_scheduler_idle_while_spinning() {
+        let state = GameState { is_spinning: true, betting_end_time: Some(0), ..GameState::new() };
+        assert_eq!(state.due_action(1_000, &auto_config()), None);
+    }
+
+    // ==================== OUTCOME PREVIEW TESTS (5 tests) ====================
+
+    fn layout(bet_type: BetType, numbers: Vec<u8>, amount: u64) -> BetLayout {
+        BetLayout { bet_type, numbers, amount }
     }
 
     #[test]
-    fn test_low_payout() {
-        assert_eq!(BetType::Low.payout_multiplier(), 1);
+    fn test_preview_straight_bet() {
+        let preview = preview_outcomes(&[layout(BetType::Straight, vec![17], 10)], WheelVariant::SingleZero).unwrap();
+        assert_eq!(preview.pockets.len(), 37);
+        assert_eq!(preview.pockets[17].net, 350);
+        assert_eq!(preview.pockets[0].net, -10);
+        assert_eq!(preview.worst_case_loss, 10);
+        assert!((preview.probability_of_profit - 1.0 / 37.0).abs() < 1e-9);
+        assert!((preview.expected_value - (-10.0 / 37.0)).abs() < 1e-9);
     }
 
     #[test]
-    fn test_high_payout() {
-        assert_eq!(BetType::High.payout_multiplier(), 1);
+    fn test_preview_hedged_layout() {
+        let bets = [layout(BetType::Red, vec![], 10), layout(BetType::Black, vec![], 10)];
+        let preview = preview_outcomes(&bets, WheelVariant::SingleZero).unwrap();
+        assert_eq!(preview.total_staked, 20);
+        assert_eq!(preview.pockets[1].net, 0);
+        assert_eq!(preview.pockets[0].net, -20);
+        assert_eq!(preview.probability_of_profit, 0.0);
+        assert_eq!(preview.worst_case_loss, 20);
     }
 
     #[test]
-    fn test_dozen_payouts() {
-        assert_eq!(BetType::FirstDozen.payout_multiplier(), 2);
-        assert_eq!(BetType::SecondDozen.payout_multiplier(), 2);
-        assert_eq!(BetType::ThirdDozen.payout_multiplier(), 2);
+    fn test_preview_double_zero_wheel() {
+        let preview = preview_outcomes(&[layout(BetType::Even, vec![], 10)], WheelVariant::DoubleZero).unwrap();
+        assert_eq!(preview.pockets.len(), 38);
+        assert_eq!(preview.pockets[DOUBLE_ZERO as usize].net, -10);
+        assert!((preview.expected_value - (-20.0 / 38.0)).abs() < 1e-9);
     }
 
     #[test]
-    fn test_column_payouts() {
-        assert_eq!(BetType::FirstColumn.payout_multiplier(), 2);
-        assert_eq!(BetType::SecondColumn.payout_multiplier(), 2);
-        assert_eq!(BetType::ThirdColumn.payout_multiplier(), 2);
+    fn test_preview_empty_layout() {
+        let preview = preview_outcomes(&[], WheelVariant::SingleZero).unwrap();
+        assert!(preview.pockets.iter().all(|p| p.net == 0));
+        assert_eq!(preview.expected_value, 0.0);
+        assert_eq!(preview.worst_case_loss, 0);
     }
 
     #[test]
-    fn test_payout_calculation() {
-        let bet_amount: u64 = 100;
-        assert_eq!(bet_amount + bet_amount * BetType::Straight.payout_multiplier(), 3600);
-        assert_eq!(bet_amount + bet_amount * BetType::Red.payout_multiplier(), 200);
-        assert_eq!(bet_amount + bet_amount * BetType::FirstDozen.payout_multiplier(), 300);
+    fn test_preview_rejects_stakes_too_large_to_count() {
+        let straight = preview_outcomes(&[layout(BetType::Straight, vec![17], u64::MAX / 10)], WheelVariant::SingleZero);
+        assert_eq!(straight.unwrap_err(), BetError::InvalidAmount);
+        let total = [layout(BetType::Red, vec![], u64::MAX), layout(BetType::Black, vec![], 1)];
+        assert_eq!(preview_outcomes(&total, WheelVariant::SingleZero).unwrap_err(), BetError::InvalidAmount);
+        let unsigned = preview_outcomes(&[layout(BetType::Red, vec![], u64::MAX / 2)], WheelVariant::SingleZero);
+        assert_eq!(unsigned.unwrap_err(), BetError::InvalidAmount);
     }
 
-    // ==================== COLOR TESTS (6 tests) ====================
+    // ==================== REQUEST TRACKING TESTS (3 tests) ====================
 
-    #[test]
-    fn test_zero_is_green() {
-        assert_eq!(get_color(0), "green");
-        assert!(!is_red(0));
-        assert!(!is_black(0));
+    fn request_error(request_id: RequestId) -> RequestError {
+        RequestError { request_id, kind: RequestKind::PlaceBet, error_message: "Insufficient balance".to_string(), timestamp: 0 }
     }
 
     #[test]
-    fn test_red_numbers() {
-        let red_numbers = [1, 3, 5, 7, 9, 12, 14, 16, 18, 19, 21, 23, 25, 27, 30, 32, 34, 36];
-        for n in red_numbers {
-            assert!(is_red(n), "Number {} should be red", n);
-            assert!(!is_black(n), "Number {} should not be black", n);
-            assert_eq!(get_color(n), "red");
-        }
+    fn test_pending_request_expiry() {
+        let request = PendingRequest { request_id: 1, kind: RequestKind::PlaceBet, table_id: 0, amount: 10, sent_at: 1_000 };
+        assert!(!request.is_expired(1_000 + REQUEST_TIMEOUT_MS - 1));
+        assert!(request.is_expired(1_000 + REQUEST_TIMEOUT_MS));
     }
 
     #[test]
-    fn test_black_numbers() {
-        let black_numbers = [2, 4, 6, 8, 10, 11, 13, 15, 17, 20, 22, 24, 26, 28, 29, 31, 33, 35];
-        for n in black_numbers {
-            assert!(is_black(n), "Number {} should be black", n);
-            assert!(!is_red(n), "Number {} should not be red", n);
-            assert_eq!(get_color(n), "black");
+    fn test_request_errors_are_bounded() {
+        let mut errors = Vec::new();
+        for request_id in 0..(LAST_ERRORS_LEN as u64 + 3) {
+            push_request_error(&mut errors, request_error(request_id));
         }
+        assert_eq!(errors.len(), LAST_ERRORS_LEN);
+        assert_eq!(errors[0].request_id, 3);
+        assert_eq!(errors.last().unwrap().request_id, LAST_ERRORS_LEN as u64 + 2);
     }
 
     #[test]
-    fn test_all_numbers_have_color() {
-        for n in 0..=36 {
-            let color = get_color(n);
-            assert!(color == "green" || color == "red" || color == "black");
-        }
+    fn test_request_errors_keep_order() {
+        let mut errors = Vec::new();
+        push_request_error(&mut errors, request_error(7));
+        push_request_error(&mut errors, request_error(8));
+        assert_eq!(errors.iter().map(|e| e.request_id).collect::<Vec<_>>(), vec![7, 8]);
     }
 
-    #[test]
-    fn test_color_count() {
-        let red_count = (1..=36).filter(|&n| is_red(n)).count();
-        let black_count = (1..=36).filter(|&n| is_black(n)).count();
-        assert_eq!(red_count, 18);
-        assert_eq!(black_count, 18);
+    // ==================== BOUNCE TESTS (10 tests) ====================
+
+    fn confirmed_player() -> Player {
+        Player { chain_id: "alice".to_string(), name: "Alice".to_string(), balance: 90, table_id: 0 }
     }
 
     #[test]
-    fn test_zero_not_red_not_black() {
-        assert!(!is_red(0));
-        assert!(!is_black(0));
+    fn test_bounced_register_request_fails_request() {
+        let message = Message::RegisterPlayerRequest { request_id: 4, table_id: 0, name: "Alice".to_string(), initial_balance: 100, invite_code: None };
+        assert_eq!(bounce_action(&message), BounceAction::FailRequest(4));
     }
 
-    // ==================== STRAIGHT BET TESTS (4 tests) ====================
+    #[test]
+    fn test_bounced_bet_request_fails_request() {
+        let message = Message::PlaceBetRequest { request_id: 9, table_id: 0, bet_type: BetType::Red, numbers: vec![], amount: 10, player_name: "Alice".to_string() };
+        assert_eq!(bounce_action(&message), BounceAction::FailRequest(9));
+    }
 
     #[test]
-    fn test_straight_bet_wins_exact() {
-        assert!(BetType::Straight.is_winner(17, &[17]));
-        assert!(BetType::Straight.is_winner(0, &[0]));
-        assert!(BetType::Straight.is_winner(36, &[36]));
+    fn test_bounced_leave_request_cancels_leave() {
+        assert_eq!(bounce_action(&Message::LeaveTableRequest { request_id: 3 }), BounceAction::CancelLeave(3));
     }
 
     #[test]
-    fn test_straight_bet_loses_different() {
-        assert!(!BetType::Straight.is_winner(17, &[18]));
-        assert!(!BetType::Straight.is_winner(0, &[1]));
-        assert!(!BetType::Straight.is_winner(5, &[10, 15, 20]));
+    fn test_bounced_register_confirm_releases_seat() {
+        let accepted = Message::PlayerRegisteredConfirm { request_id: 1, player: confirmed_player(), success: true, error_message: None };
+        let rejected = Message::PlayerRegisteredConfirm { request_id: 1, player: confirmed_player(), success: false, error_message: None };
+        assert_eq!(bounce_action(&accepted), BounceAction::ReleaseSeat("alice".to_string()));
+        assert_eq!(bounce_action(&rejected), BounceAction::Ignore);
     }
 
     #[test]
-    fn test_straight_bet_multiple_numbers() {
-        assert!(BetType::Straight.is_winner(5, &[1, 5, 10]));
-        assert!(!BetType::Straight.is_winner(7, &[1, 5, 10]));
+    fn test_bounced_bet_confirm_refunds_bet() {
+        let bet = test_bet("alice", 10);
+        let accepted = Message::BetPlacedConfirm { request_id: 2, bet: bet.clone(), success: true, new_balance: 90, error_message: None };
+        let rejected = Message::BetPlacedConfirm { request_id: 2, bet: bet.clone(), success: false, new_balance: 100, error_message: None };
+        assert_eq!(bounce_action(&accepted), BounceAction::RefundBet(bet));
+        assert_eq!(bounce_action(&rejected), BounceAction::Ignore);
     }
 
     #[test]
-    fn test_straight_bet_empty_numbers() {
-        assert!(!BetType::Straight.is_winner(17, &[]));
+    fn test_bounced_payouts_are_retried() {
+        let spin = Message::SpinResultBroadcast { table_id: 0, number: 7, player_payout: 20, new_balance: 110, won: true };
+        let update = Message::BalanceUpdate { new_balance: 110, reason: "refund".to_string() };
+        let leave = Message::LeaveTableConfirm { request_id: None, player: confirmed_player(), success: true, kicked: true, error_message: None };
+        assert_eq!(bounce_action(&spin), BounceAction::Retry);
+        assert_eq!(bounce_action(&update), BounceAction::Retry);
+        assert_eq!(bounce_action(&leave), BounceAction::Retry);
     }
 
-    // ==================== RED/BLACK BET TESTS (4 tests) ====================
+    #[test]
+    fn test_bounced_notifications_are_ignored() {
+        let spin_request = Message::SpinWheelRequest { request_id: 5, table_id: 0, player_chain_id: "alice".to_string() };
+        let failed_leave = Message::LeaveTableConfirm { request_id: Some(3), player: confirmed_player(), success: false, kicked: false, error_message: None };
+        assert_eq!(bounce_action(&spin_request), BounceAction::Ignore);
+        assert_eq!(bounce_action(&failed_leave), BounceAction::Ignore);
+        assert_eq!(bounce_action(&Message::AnnounceTables { tables: vec![] }), BounceAction::Ignore);
+        assert_eq!(bounce_action(&Message::BalanceSnapshotRequest { request_id: 1 }), BounceAction::Ignore);
+        assert_eq!(bounce_action(&Message::BalanceSnapshot { request_id: 1, player: None }), BounceAction::Ignore);
+    }
 
     #[test]
-    fn test_red_bet_wins() {
-        assert!(BetType::Red.is_winner(1, &[]));
-        assert!(BetType::Red.is_winner(19, &[]));
-        assert!(BetType::Red.is_winner(36, &[]));
+    fn test_undelivered_gives_up_after_max_attempts() {
+        let mut queue = Undelivered::default();
+        for _ in 0..MAX_DELIVERY_ATTEMPTS {
+            assert!(queue.push(Message::BalanceUpdate { new_balance: 1, reason: String::new() }));
+        }
+        assert_eq!(queue.messages.len(), MAX_DELIVERY_ATTEMPTS as usize);
+        assert!(!queue.push(Message::BalanceUpdate { new_balance: 1, reason: String::new() }));
+        assert!(queue.messages.is_empty());
     }
 
     #[test]
-    fn test_red_bet_loses() {
-        assert!(!BetType::Red.is_winner(0, &[]));
-        assert!(!BetType::Red.is_winner(2, &[]));
-        assert!(!BetType::Red.is_winner(17, &[]));
+    fn test_withdraw_bet_removes_one_match() {
+        let mut game = GameState::new();
+        game.current_bets = vec![test_bet("alice", 10), test_bet("alice", 10), test_bet("bob", 10)];
+        assert!(game.withdraw_bet(&test_bet("alice", 10)));
+        assert_eq!(game.current_bets.len(), 2);
+        assert!(!game.withdraw_bet(&test_bet("carol", 10)));
     }
 
     #[test]
-    fn test_black_bet_wins() {
-        assert!(BetType::Black.is_winner(2, &[]));
-        assert!(BetType::Black.is_winner(17, &[]));
-        assert!(BetType::Black.is_winner(35, &[]));
+    fn test_withdraw_bet_refused_while_spinning() {
+        let mut game = GameState { is_spinning: true, current_bets: vec![test_bet("alice", 10)], ..GameState::new() };
+        assert!(!game.withdraw_bet(&test_bet("alice", 10)));
+        game.apply_event(&RouletteEvent::BetRefunded { bet: test_bet("alice", 10), timestamp: String::new() });
+        assert_eq!(game.current_bets.len(), 1);
     }
 
+    // ==================== NONCE TESTS (6 tests) ====================
+
     #[test]
-    fn test_black_bet_loses() {
-        assert!(!BetType::Black.is_winner(0, &[]));
-        assert!(!BetType::Black.is_winner(1, &[]));
-        assert!(!BetType::Black.is_winner(36, &[]));
+    fn test_first_nonce_accepted() {
+        assert_eq!(check_nonce(None, 0), Ok(()));
     }
 
-    // ==================== EVEN/ODD BET TESTS (4 tests) ====================
+    #[test]
+    fn test_newer_nonce_accepted_with_gap() {
+        assert_eq!(check_nonce(Some(3), 4), Ok(()));
+        assert_eq!(check_nonce(Some(3), 9), Ok(()));
+    }
 
     #[test]
-    fn test_even_bet_wins() {
-        assert!(BetType::Even.is_winner(2, &[]));
-        assert!(BetType::Even.is_winner(18, &[]));
-        assert!(BetType::Even.is_winner(36, &[]));
+    fn test_duplicate_nonce_rejected() {
+        assert_eq!(check_nonce(Some(3), 3), Err(NonceError::Duplicate(3)));
+        assert_eq!(NonceError::Duplicate(3).code(), "DUPLICATE_REQUEST");
     }
 
     #[test]
-    fn test_even_bet_loses_on_odd() {
-        assert!(!BetType::Even.is_winner(1, &[]));
-        assert!(!BetType::Even.is_winner(17, &[]));
-        assert!(!BetType::Even.is_winner(35, &[]));
+    fn test_older_nonce_rejected() {
+        assert_eq!(check_nonce(Some(3), 1), Err(NonceError::OutOfOrder { nonce: 1, last: 3 }));
+        assert_eq!(NonceError::OutOfOrder { nonce: 1, last: 3 }.to_string(), "Request 1 arrived after request 3");
     }
 
     #[test]
-    fn test_odd_bet_wins() {
-        assert!(BetType::Odd.is_winner(1, &[]));
-        assert!(BetType::Odd.is_winner(17, &[]));
-        assert!(BetType::Odd.is_winner(35, &[]));
+    fn test_request_id_only_on_player_requests() {
+        assert_eq!(Message::LeaveTableRequest { request_id: 2 }.request_id(), Some(2));
+        assert_eq!(Message::SpinWheelRequest { request_id: 5, table_id: 0, player_chain_id: String::new() }.request_id(), Some(5));
+        assert_eq!(Message::BalanceUpdate { new_balance: 0, reason: String::new() }.request_id(), None);
     }
 
     #[test]
-    fn test_even_odd_zero_loses() {
-        assert!(!BetType::Even.is_winner(0, &[]));
-        assert!(!BetType::Odd.is_winner(0, &[]));
+    fn test_host_replies_are_not_requests() {
+        let leave = Message::LeaveTableConfirm { request_id: None, player: confirmed_player(), success: true, kicked: true, error_message: None };
+        assert!(leave.is_host_reply());
+        assert!(Message::BalanceUpdate { new_balance: 0, reason: String::new() }.is_host_reply());
+        assert!(!Message::LeaveTableRequest { request_id: 2 }.is_host_reply());
+        assert!(!Message::AnnounceTables { tables: vec![] }.is_host_reply());
     }
 
-    // ==================== LOW/HIGH BET TESTS (4 tests) ====================
+    // ==================== ROOM REGISTRY TESTS (6 tests) ====================
+
+    fn room() -> Room {
+        Room::new("chain-a".to_string(), "Room 1".to_string())
+    }
 
     #[test]
-    fn test_low_bet_wins() {
-        assert!(BetType::Low.is_winner(1, &[]));
-        assert!(BetType::Low.is_winner(10, &[]));
-        assert!(BetType::Low.is_winner(18, &[]));
+    fn test_new_room_is_available() {
+        assert!(room().is_available(0));
     }
 
     #[test]
-    fn test_low_bet_loses() {
-        assert!(!BetType::Low.is_winner(0, &[]));
-        assert!(!BetType::Low.is_winner(19, &[]));
-        assert!(!BetType::Low.is_winner(36, &[]));
+    fn test_claimed_room_is_taken() {
+        let mut room = room();
+        assert_eq!(room.claim("alice", 1_000), Ok(()));
+        assert!(!room.is_available(1_000));
+        assert_eq!(room.claim("bob", 2_000), Err(RoomError::RoomTaken));
+        assert_eq!(room.claim("alice", 2_000), Ok(()));
     }
 
     #[test]
-    fn test_high_bet_wins() {
-        assert!(BetType::High.is_winner(19, &[]));
-        assert!(BetType::High.is_winner(25, &[]));
-        assert!(BetType::High.is_winner(36, &[]));
+    fn test_lease_expires_without_heartbeat() {
+        let mut room = room();
+        room.claim("alice", 1_000).unwrap();
+        assert!(room.is_available(1_000 + ROOM_LEASE_MS));
+        assert_eq!(room.claim("bob", 1_000 + ROOM_LEASE_MS), Ok(()));
+        assert_eq!(room.claimed_by.as_deref(), Some("bob"));
     }
 
     #[test]
-    fn test_high_bet_loses() {
-        assert!(!BetType::High.is_winner(0, &[]));
-        assert!(!BetType::High.is_winner(1, &[]));
-        assert!(!BetType::High.is_winner(18, &[]));
+    fn test_heartbeat_extends_lease() {
+        let mut room = room();
+        room.claim("alice", 1_000).unwrap();
+        assert_eq!(room.heartbeat("alice", 100_000), Ok(()));
+        assert!(!room.is_available(1_000 + ROOM_LEASE_MS));
+        assert_eq!(room.heartbeat("bob", 100_000), Err(RoomError::NotClaimant));
     }
 
-    // ==================== DOZEN BET TESTS (6 tests) ====================
+    #[test]
+    fn test_release_frees_room() {
+        let mut room = room();
+        room.claim("alice", 1_000).unwrap();
+        assert_eq!(room.release("bob", 2_000), Err(RoomError::NotClaimant));
+        assert_eq!(room.release("alice", 2_000), Ok(()));
+        assert!(room.is_available(2_000));
+    }
 
     #[test]
-    fn test_first_dozen_wins() {
-        for n in 1..=12 {
-            assert!(BetType::FirstDozen.is_winner(n, &[]), "FirstDozen should win on {}", n);
-        }
+    fn test_expired_claimant_cannot_heartbeat() {
+        let mut room = room();
+        room.claim("alice", 1_000).unwrap();
+        assert_eq!(room.heartbeat("alice", 1_000 + ROOM_LEASE_MS), Err(RoomError::NotClaimant));
     }
 
+    // ==================== TABLE RESET TESTS (2 tests) ====================
+
     #[test]
-    fn test_first_dozen_loses() {
-        assert!(!BetType::FirstDozen.is_winner(0, &[]));
-        assert!(!BetType::FirstDozen.is_winner(13, &[]));
-        assert!(!BetType::FirstDozen.is_winner(36, &[]));
+    fn test_open_stakes_per_player() {
+        let mut game = GameState::new();
+        game.current_bets = vec![test_bet("alice", 10), test_bet("bob", 5), test_bet("alice", 15)];
+        let stakes = game.open_stakes();
+        assert_eq!(stakes.get("alice"), Some(&25));
+        assert_eq!(stakes.get("bob"), Some(&5));
     }
 
     #[test]
-    fn test_second_dozen_wins() {
-        for n in 13..=24 {
-            assert!(BetType::SecondDozen.is_winner(n, &[]), "SecondDozen should win on {}", n);
-        }
+    fn test_mirrored_reset_clears_table() {
+        let mut game = GameState { history: vec![3, 17], betting_end_time: Some(500), ..GameState::new() };
+        game.current_bets.push(test_bet("alice", 10));
+        game.apply_event(&RouletteEvent::TableReset { table_id: 0, session_id: 1, refunded: 10, paid_out: 0, archived: 110, timestamp: String::new() });
+        assert!(game.current_bets.is_empty());
+        assert!(game.history.is_empty());
+        assert_eq!(game.betting_end_time, None);
     }
 
+    // ==================== SCHEMA MIGRATION TESTS (3 tests) ====================
+
+    /// BCS encoding of a version 1 `Player { chain_id: "c1", name: "Al", balance: 100 }`.
+    const PLAYER_V1_FIXTURE: [u8; 14] = [2, 99, 49, 2, 65, 108, 100, 0, 0, 0, 0, 0, 0, 0];
+
+    /// BCS encoding of a version 1 `GameState` with one 10-chip red bet, history `[7]`
+    /// and betting open until 500.
+    const GAME_STATE_V1_FIXTURE: [u8; 30] = [
+        0, 1, 2, 99, 49, 2, 65, 108, 1, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 1, 7, 1, 244, 1, 0, 0, 0, 0, 0, 0,
+    ];
+
     #[test]
-    fn test_second_dozen_loses() {
-        assert!(!BetType::SecondDozen.is_winner(0, &[]));
-        assert!(!BetType::SecondDozen.is_winner(12, &[]));
-        assert!(!BetType::SecondDozen.is_winner(25, &[]));
+    fn test_player_v1_fixture_upgrades() {
+        assert!(bcs::from_bytes::<Player>(&PLAYER_V1_FIXTURE).is_err());
+        let player = bcs::from_bytes::<PlayerV1>(&PLAYER_V1_FIXTURE).unwrap().upgrade();
+        assert_eq!(player.chain_id, "c1");
+        assert_eq!(player.name, "Al");
+        assert_eq!(player.balance, 100);
+        assert_eq!(player.table_id, DEFAULT_TABLE_ID);
     }
 
     #[test]
-    fn test_third_dozen_wins() {
-        for n in 25..=36 {
-            assert!(BetType::ThirdDozen.is_winner(n, &[]), "ThirdDozen should win on {}", n);
-        }
+    fn test_game_state_v1_fixture_upgrades() {
+        assert!(bcs::from_bytes::<GameState>(&GAME_STATE_V1_FIXTURE).is_err());
+        let game = bcs::from_bytes::<GameStateV1>(&GAME_STATE_V1_FIXTURE).unwrap().upgrade();
+        assert!(!game.is_spinning);
+        assert_eq!(game.current_bets.len(), 1);
+        assert_eq!(game.current_bets[0].bet_type, BetType::Red);
+        assert_eq!(game.current_bets[0].amount, 10);
+        assert_eq!(game.current_bets[0].table_id, DEFAULT_TABLE_ID);
+        assert_eq!(game.history, vec![7]);
+        assert_eq!(game.betting_end_time, Some(500));
+        assert_eq!(game.next_round_time, None);
     }
 
     #[test]
-    fn test_third_dozen_loses() {
-        assert!(!BetType::ThirdDozen.is_winner(0, &[]));
-        assert!(!BetType::ThirdDozen.is_winner(24, &[]));
+    fn test_upgraded_state_round_trips() {
+        let game = bcs::from_bytes::<GameStateV1>(&GAME_STATE_V1_FIXTURE).unwrap().upgrade();
+        let bytes = bcs::to_bytes(&game).unwrap();
+        let decoded: GameState = bcs::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.current_bets, game.current_bets);
+        assert_eq!(decoded.history, game.history);
     }
 
-    // ==================== COLUMN BET TESTS (6 tests) ====================
+    // ==================== SETTLEMENT TESTS (6 tests) ====================
 
-    #[test]
-    fn test_first_column_wins() {
-        let first_col = [1, 4, 7, 10, 13, 16, 19, 22, 25, 28, 31, 34];
-        for n in first_col {
-            assert!(BetType::FirstColumn.is_winner(n, &[]), "FirstColumn should win on {}", n);
+    /// Winning return per chip staked, written out independently of `payout_multiplier`.
+    fn expected_return(bet_type: BetType) -> u64 {
+        match bet_type {
+            BetType::Straight => 36,
+            BetType::FirstDozen | BetType::SecondDozen | BetType::ThirdDozen => 3,
+            BetType::FirstColumn | BetType::SecondColumn | BetType::ThirdColumn => 3,
+            _ => 2,
         }
     }
 
-    #[test]
-    fn test_first_column_loses() {
-        assert!(!BetType::FirstColumn.is_winner(0, &[]));
-        assert!(!BetType::FirstColumn.is_winner(2, &[]));
-        assert!(!BetType::FirstColumn.is_winner(3, &[]));
+    fn chain(index: usize) -> String {
+        format!("chain-{}", index)
     }
 
-    #[test]
-    fn test_second_column_wins() {
-        let second_col = [2, 5, 8, 11, 14, 17, 20, 23, 26, 29, 32, 35];
-        for n in second_col {
-            assert!(BetType::SecondColumn.is_winner(n, &[]), "SecondColumn should win on {}", n);
+    /// Mostly table-sized amounts, with some near `u64::MAX` to catch overflow.
+    fn arb_amount() -> impl Strategy<Value = u64> {
+        prop_oneof![4 => 1u64..10_000, 1 => u64::MAX / 40..=u64::MAX]
+    }
+
+    prop_compose! {
+        fn arb_bet(players: usize)(player in 0..players, bet_type in prop::sample::select(BetType::ALL.to_vec()), number in 0u8..38, amount in arb_amount()) -> Bet {
+            let numbers = if bet_type == BetType::Straight { vec![number] } else { vec![] };
+            Bet { table_id: DEFAULT_TABLE_ID, player_chain_id: chain(player), player_name: format!("P{}", player), bet_type, numbers, amount }
+        }
+    }
+
+    prop_compose! {
+        /// A round's bets, the balances of the players still seated, and the winning pocket.
+        fn arb_round()(players in 1usize..6)(
+            bets in prop::collection::vec(arb_bet(players), 0..30),
+            seated in prop::collection::vec(prop::option::weighted(0.8, arb_amount()), players),
+            result in 0u8..38,
+        ) -> (Vec<Bet>, BTreeMap<String, u64>, u8) {
+            let balances = seated.into_iter().enumerate().filter_map(|(i, balance)| balance.map(|b| (chain(i), b))).collect();
+            (bets, balances, result)
         }
     }
 
     #[test]
-    fn test_second_column_loses() {
-        assert!(!BetType::SecondColumn.is_winner(0, &[]));
-        assert!(!BetType::SecondColumn.is_winner(3, &[]));
-        assert!(!BetType::SecondColumn.is_winner(4, &[]));
+    fn test_settlement_pays_seated_winners_only() {
+        let bet = |player: &str, bet_type, amount| Bet {
+            table_id: DEFAULT_TABLE_ID,
+            player_chain_id: player.to_string(),
+            player_name: player.to_string(),
+            bet_type,
+            numbers: vec![],
+            amount,
+        };
+        let bets = vec![bet("a", BetType::Red, 10), bet("a", BetType::Even, 5), bet("b", BetType::Black, 20), bet("gone", BetType::Red, 7)];
+        let balances = BTreeMap::from([("a".to_string(), 85), ("b".to_string(), 80)]);
+        // 1 is red and odd.
+        let settlement = settle_bets(&bets, 1, &balances);
+        assert_eq!(settlement.payouts["a"], PlayerPayout { payout: 20, new_balance: 105, won: true });
+        assert_eq!(settlement.payouts["b"], PlayerPayout { payout: 0, new_balance: 80, won: false });
+        assert!(!settlement.payouts.contains_key("gone"));
+        assert_eq!(settlement.winners.len(), 1);
+        assert!(settlement.bets[3].won);
+        assert_eq!(settlement.bets[3].payout, 0);
+        assert_eq!(settlement.house_net, 42 - 20);
     }
 
     #[test]
-    fn test_third_column_wins() {
-        let third_col = [3, 6, 9, 12, 15, 18, 21, 24, 27, 30, 33, 36];
-        for n in third_col {
-            assert!(BetType::ThirdColumn.is_winner(n, &[]), "ThirdColumn should win on {}", n);
+    fn test_every_bet_type_is_fair_on_36_pockets() {
+        for bet_type in BetType::ALL {
+            let winning = (1..=36).filter(|&n| bet_type.is_winner(n, &[17])).count() as u64;
+            assert_eq!(winning * expected_return(bet_type), 36, "{:?}", bet_type);
+            assert!(!bet_type.is_winner(0, &[]) && !bet_type.is_winner(DOUBLE_ZERO, &[]));
         }
     }
 
-    #[test]
-    fn test_third_column_loses() {
-        assert!(!BetType::ThirdColumn.is_winner(0, &[]));
-        assert!(!BetType::ThirdColumn.is_winner(1, &[]));
-        assert!(!BetType::ThirdColumn.is_winner(2, &[]));
+    proptest! {
+        #[test]
+        fn prop_settlement_conserves_chips((bets, balances, result) in arb_round()) {
+            let settlement = settle_bets(&bets, result, &balances);
+            let before: i128 = balances.values().map(|&b| b as i128).sum();
+            let after: i128 = balances
+                .iter()
+                .map(|(chain_id, balance)| settlement.payouts.get(chain_id).map_or(*balance, |p| p.new_balance) as i128)
+                .sum();
+            let staked: i128 = bets.iter().map(|b| b.amount as i128).sum();
+            prop_assert_eq!(before + staked, after + settlement.house_net);
+        }
+
+        #[test]
+        fn prop_balances_only_grow_by_payouts((bets, balances, result) in arb_round()) {
+            let settlement = settle_bets(&bets, result, &balances);
+            for (chain_id, payout) in &settlement.payouts {
+                let before = balances[chain_id];
+                prop_assert_eq!(payout.new_balance as u128, before as u128 + payout.payout as u128);
+                let credited: u128 = settlement.bets.iter().filter(|s| &s.bet.player_chain_id == chain_id).map(|s| s.payout as u128).sum();
+                prop_assert_eq!(payout.payout as u128, credited);
+            }
+        }
+
+        #[test]
+        fn prop_payouts_match_bet_type((bets, balances, result) in arb_round()) {
+            let settlement = settle_bets(&bets, result, &balances);
+            for settled in &settlement.bets {
+                let bet = &settled.bet;
+                prop_assert_eq!(settled.won, bet.bet_type.is_winner(result, &bet.numbers));
+                let full = bet.amount as u128 * expected_return(bet.bet_type) as u128;
+                match settlement.payouts.get(&bet.player_chain_id) {
+                    Some(_) if !settled.won => prop_assert_eq!(settled.payout, 0),
+                    // Payouts are only cut short once the balance is full.
+                    Some(payout) if payout.new_balance < u64::MAX => prop_assert_eq!(settled.payout as u128, full),
+                    Some(_) => prop_assert!(settled.payout as u128 <= full),
+                    None => prop_assert_eq!(settled.payout, 0),
+                }
+            }
+            let paid = settlement.bets.iter().filter(|s| s.won && balances.contains_key(&s.bet.player_chain_id)).count();
+            prop_assert_eq!(settlement.winners.len(), paid);
+        }
+
+        #[test]
+        fn prop_every_bet_settled_once((bets, balances, result) in arb_round()) {
+            let settlement = settle_bets(&bets, result, &balances);
+            let settled: Vec<&Bet> = settlement.bets.iter().map(|s| &s.bet).collect();
+            prop_assert_eq!(settled, bets.iter().collect::<Vec<_>>());
+            let bettors: std::collections::BTreeSet<&String> =
+                bets.iter().map(|b| &b.player_chain_id).filter(|id| balances.contains_key(*id)).collect();
+            prop_assert_eq!(settlement.payouts.keys().collect::<std::collections::BTreeSet<_>>(), bettors);
+        }
     }
 
-    // ==================== EDGE CASE TESTS (4 tests) ====================
+    // ==================== AUTOPLAY TESTS (7 tests) ====================
+
+    fn autoplay(rounds: u32, stop_loss: Option<u64>, take_profit: Option<u64>) -> Autoplay {
+        let bets = vec![BetLayout { bet_type: BetType::Red, numbers: vec![], amount: 10 }];
+        Autoplay::new(DEFAULT_TABLE_ID, bets, rounds, stop_loss, take_profit, 100).unwrap()
+    }
 
     #[test]
-    fn test_zero_loses_all_outside_bets() {
-        assert!(!BetType::Red.is_winner(0, &[]));
-        assert!(!BetType::Black.is_winner(0, &[]));
-        assert!(!BetType::Even.is_winner(0, &[]));
-        assert!(!BetType::Odd.is_winner(0, &[]));
-        assert!(!BetType::Low.is_winner(0, &[]));
-        assert!(!BetType::High.is_winner(0, &[]));
-        assert!(!BetType::FirstDozen.is_winner(0, &[]));
-        assert!(!BetType::SecondDozen.is_winner(0, &[]));
-        assert!(!BetType::ThirdDozen.is_winner(0, &[]));
-        assert!(!BetType::FirstColumn.is_winner(0, &[]));
-        assert!(!BetType::SecondColumn.is_winner(0, &[]));
-        assert!(!BetType::ThirdColumn.is_winner(0, &[]));
+    fn test_autoplay_needs_bets_and_rounds() {
+        let bets = vec![BetLayout { bet_type: BetType::Red, numbers: vec![], amount: 10 }];
+        assert_eq!(Autoplay::new(0, vec![], 5, None, None, 100).unwrap_err(), AutoplayError::NoBets);
+        assert_eq!(Autoplay::new(0, bets, 0, None, None, 100).unwrap_err(), AutoplayError::NoRounds);
     }
 
     #[test]
-    fn test_zero_wins_straight_bet() {
-        assert!(BetType::Straight.is_winner(0, &[0]));
+    fn test_autoplay_rejects_zero_amounts() {
+        let bets = vec![
+            BetLayout { bet_type: BetType::Red, numbers: vec![], amount: 10 },
+            BetLayout { bet_type: BetType::Black, numbers: vec![], amount: 0 },
+        ];
+        assert_eq!(Autoplay::new(0, bets, 5, None, None, 100).unwrap_err(), AutoplayError::ZeroAmount);
     }
 
     #[test]
-    fn test_boundary_numbers() {
-        assert!(BetType::Low.is_winner(1, &[]));
-        assert!(BetType::Low.is_winner(18, &[]));
-        assert!(!BetType::Low.is_winner(19, &[]));
-        assert!(BetType::High.is_winner(19, &[]));
-        assert!(BetType::High.is_winner(36, &[]));
-        assert!(!BetType::High.is_winner(18, &[]));
+    fn test_autoplay_rejects_overflowing_stake() {
+        let bet = |amount| BetLayout { bet_type: BetType::Red, numbers: vec![], amount };
+        assert_eq!(Autoplay::new(0, vec![bet(u64::MAX), bet(1)], 5, None, None, 100).unwrap_err(), AutoplayError::StakeTooLarge);
+        let plan = Autoplay::new(0, vec![bet(u64::MAX - 1), bet(1)], 5, None, None, u64::MAX).unwrap();
+        assert_eq!(plan.stake(), u64::MAX);
     }
 
     #[test]
-    fn test_all_numbers_valid_range() {
-        for n in 0..=36 {
-            let _ = get_color(n);
-            let _ = BetType::Straight.is_winner(n, &[n]);
-        }
+    fn test_autoplay_stops_after_its_rounds() {
+        let mut plan = autoplay(2, None, None);
+        assert!(plan.can_continue(100));
+        assert!(plan.round_settled(110));
+        assert!(!plan.round_settled(120));
+        assert_eq!(plan.rounds_played, 2);
+        assert_eq!(plan.stopped, Some(AutoplayStop::RoundsCompleted));
     }
 
-    // ==================== GAME STATE TESTS (2 tests) ====================
+    #[test]
+    fn test_autoplay_stop_loss_and_take_profit() {
+        let mut losing = autoplay(10, Some(20), Some(30));
+        assert!(losing.round_settled(90));
+        assert!(!losing.round_settled(80));
+        assert_eq!(losing.stopped, Some(AutoplayStop::StopLoss));
+
+        let mut winning = autoplay(10, Some(20), Some(30));
+        assert!(winning.round_settled(120));
+        assert!(!winning.round_settled(130));
+        assert_eq!(winning.stopped, Some(AutoplayStop::TakeProfit));
+    }
 
     #[test]
-    fn test_game_state_default() {
-        let state = GameState::new();
-        assert!(!state.is_spinning);
-        assert!(state.current_bets.is_empty());
-        assert!(state.last_result.is_none());
-        assert!(state.history.is_empty());
-        assert!(state.betting_end_time.is_none());
+    fn test_autoplay_stops_when_stake_is_unaffordable() {
+        let mut plan = autoplay(10, None, None);
+        assert!(!plan.round_settled(9));
+        assert_eq!(plan.stopped, Some(AutoplayStop::InsufficientBalance));
     }
 
     #[test]
-    fn test_instantiation_argument_default() {
-        let arg = InstantiationArgument::default();
-        assert!(arg.host_chain_id.is_none());
+    fn test_stopped_autoplay_stays_stopped() {
+        let mut plan = autoplay(10, None, None);
+        plan.stop(AutoplayStop::Stopped);
+        plan.stop(AutoplayStop::Unseated);
+        assert!(!plan.round_settled(100));
+        assert!(!plan.can_continue(100));
+        assert_eq!(plan.rounds_played, 0);
+        assert_eq!(plan.stopped, Some(AutoplayStop::Stopped));
     }
 }
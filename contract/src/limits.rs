@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Responsible-gaming controls the host keeps for every player chain: loss and
+//! wager limits per day and per week, cool-off periods and self-exclusion.
+//! Days and weeks are fixed windows counted from the Unix epoch.
+
+use serde::{Deserialize, Serialize};
+
+use crate::BetError;
+
+pub const DAY_MS: u64 = 86_400_000;
+pub const WEEK_MS: u64 = 7 * DAY_MS;
+/// How long a player waits before a looser limit applies. Tighter limits apply at once.
+pub const LOOSENING_DELAY_MS: u64 = DAY_MS;
+
+/// Caps on play; `None` means unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(rename_fields = "camelCase", input_name = "PlayerLimitsInput")]
+pub struct PlayerLimits {
+    #[graphql(default)]
+    pub daily_loss: Option<u64>,
+    #[graphql(default)]
+    pub weekly_loss: Option<u64>,
+    #[graphql(default)]
+    pub daily_wager: Option<u64>,
+    #[graphql(default)]
+    pub weekly_wager: Option<u64>,
+}
+
+/// `amount` as a window delta, capped rather than wrapped.
+fn signed(amount: u64) -> i64 {
+    i64::try_from(amount).unwrap_or(i64::MAX)
+}
+
+fn stricter(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+impl PlayerLimits {
+    /// The stricter of the two values of every limit.
+    pub fn stricter(&self, other: &PlayerLimits) -> PlayerLimits {
+        PlayerLimits {
+            daily_loss: stricter(self.daily_loss, other.daily_loss),
+            weekly_loss: stricter(self.weekly_loss, other.weekly_loss),
+            daily_wager: stricter(self.daily_wager, other.daily_wager),
+            weekly_wager: stricter(self.weekly_wager, other.weekly_wager),
+        }
+    }
+}
+
+/// Looser limits a player asked for, waiting out `LOOSENING_DELAY_MS`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct PendingLimits {
+    pub limits: PlayerLimits,
+    pub effective_at: u64,
+}
+
+/// Chips staked and won back within one day or week.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct PlayWindow {
+    pub start: u64,
+    pub wagered: u64,
+    /// Returns minus stakes; negative while the player is down.
+    pub net: i64,
+}
+
+impl PlayWindow {
+    /// This window as of `now`, empty again once a new window of `length` has begun.
+    fn at(&self, now: u64, length: u64) -> PlayWindow {
+        let start = now - now % length;
+        if self.start == start { *self } else { PlayWindow { start, wagered: 0, net: 0 } }
+    }
+
+    pub fn loss(&self) -> u64 {
+        self.net.min(0).unsigned_abs()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LimitChange {
+    SetLimits(PlayerLimits),
+    CoolOff { duration_ms: u64 },
+    /// Irrevocable: the end time can be pushed back but never brought forward.
+    SelfExclude { until_ms: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LimitError {
+    #[error("Cool-off period must be longer than zero")]
+    EmptyCoolOff,
+    #[error("Self-exclusion must end in the future")]
+    ExclusionInPast,
+}
+
+impl LimitError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LimitError::EmptyCoolOff => "EMPTY_COOL_OFF",
+            LimitError::ExclusionInPast => "EXCLUSION_IN_PAST",
+        }
+    }
+}
+
+/// One player's settings and recent play, kept by the host.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct ResponsibleGaming {
+    pub limits: PlayerLimits,
+    pub pending: Option<PendingLimits>,
+    pub cool_off_until: Option<u64>,
+    pub excluded_until: Option<u64>,
+    pub day: PlayWindow,
+    pub week: PlayWindow,
+}
+
+impl ResponsibleGaming {
+    /// The limits in force at `now`, including a loosening whose delay has passed.
+    pub fn limits_at(&self, now: u64) -> &PlayerLimits {
+        match &self.pending {
+            Some(pending) if pending.effective_at <= now => &pending.limits,
+            _ => &self.limits,
+        }
+    }
+
+    pub fn apply(&mut self, change: LimitChange, now: u64) -> Result<(), LimitError> {
+        self.limits = self.limits_at(now).clone();
+        self.pending = self.pending.take().filter(|pending| pending.effective_at > now);
+        match change {
+            LimitChange::SetLimits(requested) => {
+                let immediate = self.limits.stricter(&requested);
+                // A new request replaces any loosening still waiting.
+                self.pending = (immediate != requested)
+                    .then(|| PendingLimits { limits: requested, effective_at: now.saturating_add(LOOSENING_DELAY_MS) });
+                self.limits = immediate;
+            }
+            LimitChange::CoolOff { duration_ms } => {
+                if duration_ms == 0 { return Err(LimitError::EmptyCoolOff); }
+                self.cool_off_until = self.cool_off_until.max(Some(now.saturating_add(duration_ms)));
+            }
+            LimitChange::SelfExclude { until_ms } => {
+                if until_ms <= now { return Err(LimitError::ExclusionInPast); }
+                self.excluded_until = self.excluded_until.max(Some(until_ms));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a stake of `amount` at `now`, assuming it is lost.
+    pub fn check_stake(&self, amount: u64, now: u64) -> Result<(), BetError> {
+        if let Some(until) = self.excluded_until.filter(|&until| until > now) {
+            return Err(BetError::SelfExcluded(until));
+        }
+        if let Some(until) = self.cool_off_until.filter(|&until| until > now) {
+            return Err(BetError::CoolingOff(until));
+        }
+        let limits = self.limits_at(now);
+        let (day, week) = (self.day.at(now, DAY_MS), self.week.at(now, WEEK_MS));
+        let exceeds = |limit: Option<u64>, used: u64| limit.is_some_and(|limit| used.saturating_add(amount) > limit);
+        if exceeds(limits.daily_loss, day.loss()) {
+            return Err(BetError::DailyLossLimit(limits.daily_loss.unwrap_or_default()));
+        }
+        if exceeds(limits.weekly_loss, week.loss()) {
+            return Err(BetError::WeeklyLossLimit(limits.weekly_loss.unwrap_or_default()));
+        }
+        if exceeds(limits.daily_wager, day.wagered) {
+            return Err(BetError::DailyWagerLimit(limits.daily_wager.unwrap_or_default()));
+        }
+        if exceeds(limits.weekly_wager, week.wagered) {
+            return Err(BetError::WeeklyWagerLimit(limits.weekly_wager.unwrap_or_default()));
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, now: u64, wagered: i64, net: i64) {
+        for (window, length) in [(&mut self.day, DAY_MS), (&mut self.week, WEEK_MS)] {
+            *window = window.at(now, length);
+            window.wagered = window.wagered.saturating_add_signed(wagered);
+            window.net = window.net.saturating_add(net);
+        }
+    }
+
+    pub fn record_stake(&mut self, amount: u64, now: u64) {
+        let amount = signed(amount);
+        self.record(now, amount, -amount);
+    }
+
+    pub fn record_payout(&mut self, amount: u64, now: u64) {
+        self.record(now, 0, signed(amount));
+    }
+
+    /// A stake handed back unplayed no longer counts as wagered.
+    pub fn record_refund(&mut self, amount: u64, now: u64) {
+        let amount = signed(amount);
+        self.record(now, -amount, amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 100 * WEEK_MS + 3 * DAY_MS + 1_000;
+
+    fn with_limits(limits: PlayerLimits) -> ResponsibleGaming {
+        let mut gaming = ResponsibleGaming::default();
+        gaming.apply(LimitChange::SetLimits(limits), NOW).unwrap();
+        gaming
+    }
+
+    #[test]
+    fn test_no_limits_allow_any_stake() {
+        let gaming = ResponsibleGaming::default();
+        assert_eq!(gaming.check_stake(u64::MAX, NOW), Ok(()));
+    }
+
+    #[test]
+    fn test_loss_limit_counts_the_stake_at_risk() {
+        let mut gaming = with_limits(PlayerLimits { daily_loss: Some(50), ..Default::default() });
+        gaming.record_stake(30, NOW);
+        assert_eq!(gaming.check_stake(20, NOW), Ok(()));
+        assert_eq!(gaming.check_stake(21, NOW), Err(BetError::DailyLossLimit(50)));
+        // Winnings win back room under the limit; refunds undo the stake.
+        gaming.record_payout(20, NOW);
+        assert_eq!(gaming.check_stake(40, NOW), Ok(()));
+        gaming.record_refund(10, NOW);
+        assert_eq!(gaming.day.net, 0);
+        assert_eq!(gaming.day.wagered, 20);
+    }
+
+    #[test]
+    fn test_wager_limits_reset_with_their_window() {
+        let mut gaming = with_limits(PlayerLimits { daily_wager: Some(100), weekly_wager: Some(150), ..Default::default() });
+        gaming.record_stake(100, NOW);
+        gaming.record_payout(200, NOW);
+        assert_eq!(gaming.check_stake(1, NOW), Err(BetError::DailyWagerLimit(100)));
+        assert_eq!(gaming.check_stake(50, NOW + DAY_MS), Ok(()));
+        assert_eq!(gaming.check_stake(51, NOW + DAY_MS), Err(BetError::WeeklyWagerLimit(150)));
+        assert_eq!(gaming.check_stake(100, NOW + WEEK_MS), Ok(()));
+    }
+
+    #[test]
+    fn test_tightening_is_immediate_and_loosening_waits() {
+        let mut gaming = with_limits(PlayerLimits { daily_loss: Some(100), ..Default::default() });
+        gaming.apply(LimitChange::SetLimits(PlayerLimits { daily_loss: Some(40), daily_wager: Some(500), ..Default::default() }), NOW).unwrap();
+        assert_eq!(gaming.limits.daily_loss, Some(40));
+        assert_eq!(gaming.limits.daily_wager, Some(500));
+        assert!(gaming.pending.is_none());
+
+        // Raising one limit and removing another both wait out the delay.
+        gaming.apply(LimitChange::SetLimits(PlayerLimits { daily_loss: Some(80), ..Default::default() }), NOW).unwrap();
+        assert_eq!(gaming.limits_at(NOW).daily_loss, Some(40));
+        assert_eq!(gaming.limits_at(NOW).daily_wager, Some(500));
+        assert_eq!(gaming.check_stake(41, NOW), Err(BetError::DailyLossLimit(40)));
+        let later = NOW + LOOSENING_DELAY_MS;
+        assert_eq!(gaming.limits_at(later).daily_loss, Some(80));
+        assert_eq!(gaming.limits_at(later).daily_wager, None);
+        assert_eq!(gaming.check_stake(80, later), Ok(()));
+    }
+
+    #[test]
+    fn test_tightening_cancels_a_pending_loosening() {
+        let mut gaming = with_limits(PlayerLimits { weekly_loss: Some(100), ..Default::default() });
+        gaming.apply(LimitChange::SetLimits(PlayerLimits::default()), NOW).unwrap();
+        assert!(gaming.pending.is_some());
+        gaming.apply(LimitChange::SetLimits(PlayerLimits { weekly_loss: Some(100), ..Default::default() }), NOW + 1).unwrap();
+        assert!(gaming.pending.is_none());
+        assert_eq!(gaming.limits_at(NOW + WEEK_MS).weekly_loss, Some(100));
+    }
+
+    #[test]
+    fn test_cool_off_blocks_until_it_ends() {
+        let mut gaming = ResponsibleGaming::default();
+        assert_eq!(gaming.apply(LimitChange::CoolOff { duration_ms: 0 }, NOW), Err(LimitError::EmptyCoolOff));
+        gaming.apply(LimitChange::CoolOff { duration_ms: DAY_MS }, NOW).unwrap();
+        // A shorter cool-off does not cut the running one short.
+        gaming.apply(LimitChange::CoolOff { duration_ms: 1 }, NOW).unwrap();
+        assert_eq!(gaming.check_stake(1, NOW + DAY_MS - 1), Err(BetError::CoolingOff(NOW + DAY_MS)));
+        assert_eq!(gaming.check_stake(1, NOW + DAY_MS), Ok(()));
+    }
+
+    #[test]
+    fn test_endless_cool_off_saturates() {
+        let mut gaming = ResponsibleGaming::default();
+        gaming.apply(LimitChange::CoolOff { duration_ms: u64::MAX }, NOW).unwrap();
+        assert_eq!(gaming.cool_off_until, Some(u64::MAX));
+        assert_eq!(gaming.check_stake(1, u64::MAX - 1), Err(BetError::CoolingOff(u64::MAX)));
+    }
+
+    #[test]
+    fn test_huge_stakes_do_not_wrap() {
+        let mut gaming = ResponsibleGaming::default();
+        gaming.record_stake(u64::MAX, NOW);
+        gaming.record_refund(u64::MAX, NOW);
+        gaming.record_stake(u64::MAX, NOW);
+        assert_eq!(gaming.day.wagered, i64::MAX as u64);
+        assert_eq!(gaming.day.loss(), i64::MAX as u64);
+    }
+
+    #[test]
+    fn test_self_exclusion_is_irrevocable() {
+        let mut gaming = ResponsibleGaming::default();
+        let until = NOW + 30 * DAY_MS;
+        assert_eq!(gaming.apply(LimitChange::SelfExclude { until_ms: NOW }, NOW), Err(LimitError::ExclusionInPast));
+        gaming.apply(LimitChange::SelfExclude { until_ms: until }, NOW).unwrap();
+        gaming.apply(LimitChange::SelfExclude { until_ms: NOW + 1 }, NOW).unwrap();
+        gaming.apply(LimitChange::SetLimits(PlayerLimits::default()), NOW + LOOSENING_DELAY_MS).unwrap();
+        assert_eq!(gaming.excluded_until, Some(until));
+        assert_eq!(gaming.check_stake(1, until - 1), Err(BetError::SelfExcluded(until)));
+        assert_eq!(gaming.check_stake(1, until), Ok(()));
+    }
+}
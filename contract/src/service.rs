@@ -4,16 +4,17 @@
 
 mod state;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
-use linera_sdk::{linera_base_types::WithServiceAbi, views::View, Service, ServiceRuntime};
-use linera_roulette::{RouletteAbi, GameState, Player};
+use linera_sdk::{linera_base_types::{ChainId, WithServiceAbi}, Service, ServiceRuntime};
+use linera_roulette::{limits::{LimitChange, LimitError, PlayerLimits, ResponsibleGaming}, strategy::{StrategyKind, StrategyRun}, preview_outcomes, validate_bet, Autoplay, AutoplayError, BetError, BetLayout, BetType, GameState, ListedTable, OutcomePreview, Operation, PendingJoin, PendingRequest, Player, RequestError, RequestKind, Room, RoomAction, RouletteAbi, TableConfig, TableId, TableInfo, WheelVariant};
 use self::state::RouletteState;
 
 linera_sdk::service!(RouletteService);
 
 pub struct RouletteService {
-    state: RouletteState,
+    state: Arc<RouletteState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -25,22 +26,20 @@ impl Service for RouletteService {
     type Parameters = ();
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
-        let state = RouletteState::load(runtime.root_view_storage_context())
-            .await
-            .expect("Failed to load state");
-        RouletteService { state, runtime: Arc::new(runtime) }
+        // Only the contract can migrate, so a chain no block has touched since version 1
+        // is refused rather than served as if it were empty.
+        let Ok(state) = RouletteState::load_current(runtime.root_view_storage_context()).await else {
+            panic!("State is in the version 1 layout until the chain's next block migrates it");
+        };
+        RouletteService { state: Arc::new(state), runtime: Arc::new(runtime) }
     }
 
     async fn handle_query(&self, request: Request) -> Response {
         let schema = Schema::build(
-            QueryRoot {
-                game: self.state.game.get().clone(),
-                players: self.get_all_players().await,
-                my_player: self.state.my_player.get().clone(),
-                is_host: *self.state.is_host.get(),
-                host_chain_id: self.state.host_chain_id.get().clone(),
-            },
-            MutationRoot { runtime: self.runtime.clone() },
+            QueryRoot { state: self.state.clone(), chain_id: self.runtime.chain_id(), now_ms: self.runtime.system_time().micros() / 1000 },
+            MutationRoot { state: self.state.clone(), runtime: self.runtime.clone() },
+            // A service answers one query against one block's state and cannot push. Clients
+            // watch the node's `notifications(chainId)` subscription and re-query on each new block.
             EmptySubscription,
         )
         .finish();
@@ -48,77 +47,582 @@ impl Service for RouletteService {
     }
 }
 
-impl RouletteService {
-    async fn get_all_players(&self) -> Vec<Player> {
-        let mut players = Vec::new();
-        self.state.players.for_each_index_value(|_, value| {
-            players.push(value.into_owned());
-            Ok(())
-        }).await.unwrap();
-        players
-    }
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, async_graphql::Enum)]
+enum PlayerSort {
+    #[default]
+    ChainId,
+    Name,
+    BalanceDesc,
 }
 
+/// Resolves every field on demand, so a query only reads the views it asks for.
 struct QueryRoot {
-    game: GameState,
-    players: Vec<Player>,
-    my_player: Option<Player>,
-    is_host: bool,
-    host_chain_id: Option<String>,
+    state: Arc<RouletteState>,
+    chain_id: ChainId,
+    now_ms: u64,
+}
+
+impl QueryRoot {
+    async fn game(&self, table_id: TableId) -> async_graphql::Result<GameState> {
+        Ok(self.state.tables.get(&table_id).await?.unwrap_or_default())
+    }
+
+    async fn table_infos(&self) -> async_graphql::Result<Vec<TableInfo>> {
+        let mut player_counts: HashMap<TableId, u64> = HashMap::new();
+        self.state.players.for_each_index_value(|_, player| {
+            *player_counts.entry(player.table_id).or_default() += 1;
+            Ok(())
+        }).await?;
+
+        let mut configs = Vec::new();
+        self.state.table_configs.for_each_index_value(|table_id, config| {
+            configs.push((table_id, config.into_owned()));
+            Ok(())
+        }).await?;
+
+        let mut tables = Vec::new();
+        for (table_id, config) in configs {
+            let game = self.game(table_id).await?;
+            let player_count = player_counts.get(&table_id).copied().unwrap_or(0);
+            tables.push(TableInfo::new(table_id, config, &game, player_count));
+        }
+        Ok(tables)
+    }
 }
 
 #[Object]
 impl QueryRoot {
-    async fn game_state(&self) -> &GameState { &self.game }
-    async fn players(&self) -> &Vec<Player> { &self.players }
-    async fn player(&self, chain_id: String) -> Option<Player> {
-        self.players.iter().find(|p| p.chain_id == chain_id).cloned()
-    }
-    async fn current_bets(&self) -> &Vec<linera_roulette::Bet> { &self.game.current_bets }
-    async fn last_result(&self) -> Option<&linera_roulette::SpinResult> { self.game.last_result.as_ref() }
-    async fn history(&self) -> &Vec<u8> { &self.game.history }
-    async fn is_spinning(&self) -> bool { self.game.is_spinning }
-    async fn player_balance(&self, chain_id: String) -> u64 {
-        self.players.iter().find(|p| p.chain_id == chain_id).map_or(0, |p| p.balance)
-    }
-    async fn total_pot(&self) -> u64 { self.game.current_bets.iter().map(|b| b.amount).sum() }
-    async fn my_player(&self) -> Option<&Player> { self.my_player.as_ref() }
-    async fn am_i_host(&self) -> bool { self.is_host }
-    async fn host_chain_id(&self) -> Option<&String> { self.host_chain_id.as_ref() }
+    async fn game_state(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<GameState> { self.game(table_id).await }
+    async fn tables(&self) -> async_graphql::Result<Vec<TableInfo>> { self.table_infos().await }
+    async fn table(&self, table_id: TableId) -> async_graphql::Result<Option<TableInfo>> {
+        let Some(config) = self.state.table_configs.get(&table_id).await? else { return Ok(None) };
+        let game = self.game(table_id).await?;
+        // Players are keyed by chain, so seats at this table still have to be counted.
+        let mut player_count = 0;
+        self.state.players.for_each_index_value(|_, player| {
+            if player.table_id == table_id { player_count += 1; }
+            Ok(())
+        }).await?;
+        Ok(Some(TableInfo::new(table_id, config, &game, player_count)))
+    }
+    async fn player_count(&self) -> async_graphql::Result<u64> { Ok(self.state.players.count().await? as u64) }
+    async fn players(
+        &self,
+        #[graphql(default)] offset: u32,
+        limit: Option<u32>,
+        #[graphql(default)] sort_by: PlayerSort,
+    ) -> async_graphql::Result<Vec<Player>> {
+        let offset = offset as usize;
+        let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+        let mut players = Vec::new();
+        if sort_by == PlayerSort::ChainId {
+            // Keys are already in chain id order, so stop reading once the page is full.
+            let mut index = 0;
+            self.state.players.for_each_index_value_while(|_, player| {
+                if players.len() >= limit { return Ok(false); }
+                if index >= offset { players.push(player.into_owned()); }
+                index += 1;
+                Ok(true)
+            }).await?;
+            return Ok(players);
+        }
+        self.state.players.for_each_index_value(|_, player| {
+            players.push(player.into_owned());
+            Ok(())
+        }).await?;
+        match sort_by {
+            PlayerSort::Name => players.sort_by(|a, b| a.name.cmp(&b.name)),
+            PlayerSort::BalanceDesc => players.sort_by(|a, b| b.balance.cmp(&a.balance)),
+            PlayerSort::ChainId => {}
+        }
+        Ok(players.into_iter().skip(offset).take(limit).collect())
+    }
+    async fn player(&self, chain_id: String) -> async_graphql::Result<Option<Player>> {
+        Ok(self.state.players.get(&chain_id).await?)
+    }
+    async fn current_bets(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<Vec<linera_roulette::Bet>> {
+        Ok(self.game(table_id).await?.current_bets)
+    }
+    async fn last_result(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<Option<linera_roulette::SpinResult>> {
+        Ok(self.game(table_id).await?.last_result)
+    }
+    async fn history(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<Vec<u8>> {
+        Ok(self.game(table_id).await?.history)
+    }
+    async fn is_spinning(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<bool> {
+        Ok(self.game(table_id).await?.is_spinning)
+    }
+    async fn player_balance(&self, chain_id: String) -> async_graphql::Result<u64> {
+        Ok(self.state.players.get(&chain_id).await?.map_or(0, |p| p.balance))
+    }
+    async fn total_pot(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<u64> {
+        Ok(self.game(table_id).await?.current_bets.iter().map(|b| b.amount).sum())
+    }
+    async fn my_player(&self) -> Option<&Player> { self.state.my_player.get().as_ref() }
+    async fn wallet_balance(&self) -> u64 { *self.state.wallet_balance.get() }
+    async fn am_i_host(&self) -> bool { *self.state.is_host.get() }
+    async fn host_chain_id(&self) -> Option<&String> { self.state.host_chain_id.get().as_ref() }
+    async fn am_i_registry(&self) -> bool { *self.state.is_registry.get() }
+    async fn am_i_spectator(&self) -> bool { *self.state.is_spectator.get() }
+    async fn am_i_solo(&self) -> bool { *self.state.is_solo.get() }
+    async fn registry_chain_id(&self) -> Option<&String> { self.state.registry_chain_id.get().as_ref() }
+    async fn pending_join(&self) -> Option<&PendingJoin> { self.state.pending_join.get().as_ref() }
+    /// Requests sent to the host that have not been confirmed, oldest first.
+    async fn pending_requests(&self) -> async_graphql::Result<Vec<PendingRequest>> {
+        let mut requests = Vec::new();
+        self.state.pending_requests.for_each_index_value(|_, request| {
+            requests.push(request.into_owned());
+            Ok(())
+        }).await?;
+        Ok(requests)
+    }
+    async fn last_errors(&self) -> &Vec<RequestError> { self.state.last_errors.get() }
+    /// The current or most recent autoplay plan of this player chain.
+    async fn autoplay(&self) -> Option<&Autoplay> { self.state.autoplay.get().as_ref() }
+    /// The current or most recent strategy run of this player chain.
+    async fn strategy(&self) -> Option<&StrategyRun> { self.state.strategy.get().as_ref() }
+    /// A player's limits, cool-off and self-exclusion; this chain's own when `chainId` is omitted.
+    /// On player chains this is the copy the host last confirmed.
+    async fn responsible_gaming(&self, chain_id: Option<String>) -> async_graphql::Result<ResponsibleGaming> {
+        let chain_id = chain_id.unwrap_or_else(|| self.chain_id.to_string());
+        Ok(self.state.responsible_gaming.get(&chain_id).await?.unwrap_or_default())
+    }
+    /// True while this chain's balance may lag the host: a bet is unconfirmed or
+    /// a round settled without the host's figures arriving yet.
+    async fn balance_is_stale(&self) -> async_graphql::Result<bool> {
+        if *self.state.balance_is_stale.get() { return Ok(true); }
+        let mut awaiting_bet = false;
+        self.state.pending_requests.for_each_index_value_while(|_, request| {
+            awaiting_bet = request.kind == RequestKind::PlaceBet;
+            Ok(!awaiting_bet)
+        }).await?;
+        Ok(awaiting_bet)
+    }
+    async fn available_tables(&self) -> async_graphql::Result<Vec<ListedTable>> {
+        let mut tables = Vec::new();
+        self.state.listings.for_each_index_value(|_, listing| {
+            let listing = listing.into_owned();
+            for table in listing.tables {
+                tables.push(ListedTable { host_chain_id: listing.host_chain_id.clone(), table });
+            }
+            Ok(())
+        }).await?;
+        Ok(tables)
+    }
+    /// Number of resets the table has been through; 0 for its first session.
+    async fn session_id(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<u64> {
+        Ok(self.state.table_sessions.get(&table_id).await?.unwrap_or(0))
+    }
+    /// Stakes the house kept from players who left mid-round at forfeit tables.
+    async fn house_funds(&self) -> u64 { *self.state.house_funds.get() }
+    async fn archived_balance(&self, chain_id: String) -> async_graphql::Result<u64> {
+        Ok(self.state.archived_balances.get(&chain_id).await?.unwrap_or(0))
+    }
+    async fn rooms(&self) -> async_graphql::Result<Vec<Room>> {
+        let mut rooms = Vec::new();
+        self.state.rooms.for_each_index_value(|_, room| {
+            rooms.push(room.into_owned());
+            Ok(())
+        }).await?;
+        Ok(rooms)
+    }
+    async fn room(&self, chain_id: String) -> async_graphql::Result<Option<Room>> {
+        Ok(self.state.rooms.get(&chain_id).await?)
+    }
+    /// Rooms nobody holds a live lease on.
+    async fn available_rooms(&self) -> async_graphql::Result<Vec<Room>> {
+        let mut rooms = Vec::new();
+        self.state.rooms.for_each_index_value(|_, room| {
+            if room.is_available(self.now_ms) { rooms.push(room.into_owned()); }
+            Ok(())
+        }).await?;
+        Ok(rooms)
+    }
+    /// The hash to put in a table's access policy for `code`; only valid for that table of this chain.
+    async fn hash_invite_code(&self, code: String, #[graphql(default)] table_id: TableId) -> String {
+        linera_roulette::hash_invite_code(&self.chain_id.to_string(), table_id, &code)
+    }
+    /// A table's allowlist. Only the host has it; it is never published.
+    async fn table_members(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<Vec<String>> {
+        Ok(self.state.table_configs.get(&table_id).await?.map(|c| c.access.members).unwrap_or_default())
+    }
+    /// What-if view of a bet layout; uses the table's wheel unless `variant` is given.
+    async fn preview_outcomes(
+        &self,
+        bets: Vec<BetLayout>,
+        #[graphql(default)] table_id: TableId,
+        variant: Option<WheelVariant>,
+    ) -> async_graphql::Result<OutcomePreview> {
+        let variant = match variant {
+            Some(variant) => variant,
+            None => self.state.table_configs.get(&table_id).await?.map(|c| c.variant).unwrap_or_default(),
+        };
+        Ok(preview_outcomes(&bets, variant)?)
+    }
+}
+
+#[derive(Debug, Clone, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct MutationResult {
+    accepted: bool,
+    error_code: Option<String>,
+    error_message: Option<String>,
+    /// The player's balance once the operation is applied, when it changes it.
+    predicted_balance: Option<u64>,
+}
+
+impl MutationResult {
+    fn accept(predicted_balance: Option<u64>) -> Self {
+        Self { accepted: true, error_code: None, error_message: None, predicted_balance }
+    }
+
+    fn reject(error_code: &str, error_message: &str) -> Self {
+        Self {
+            accepted: false,
+            error_code: Some(error_code.to_string()),
+            error_message: Some(error_message.to_string()),
+            predicted_balance: None,
+        }
+    }
 }
 
+impl From<BetError> for MutationResult {
+    fn from(error: BetError) -> Self {
+        Self::reject(error.code(), &error.to_string())
+    }
+}
+
+impl From<AutoplayError> for MutationResult {
+    fn from(error: AutoplayError) -> Self {
+        Self::reject(error.code(), &error.to_string())
+    }
+}
+
+impl From<LimitError> for MutationResult {
+    fn from(error: LimitError) -> Self {
+        Self::reject(error.code(), &error.to_string())
+    }
+}
+
+/// Pre-validates each operation against this chain's view of the game before scheduling it.
+/// Player chains validate against their mirrored tables, so acceptance is a prediction.
 struct MutationRoot {
+    state: Arc<RouletteState>,
     runtime: Arc<ServiceRuntime<RouletteService>>,
 }
 
+impl MutationRoot {
+    fn schedule(&self, operation: Operation, predicted_balance: Option<u64>) -> MutationResult {
+        self.runtime.schedule_operation(&operation);
+        MutationResult::accept(predicted_balance)
+    }
+
+    fn role_error(&self, allowed: bool, role: &str) -> Option<MutationResult> {
+        (!allowed).then(|| MutationResult::reject("WRONG_ROLE", &format!("Only {} chains can do this", role)))
+    }
+
+    fn registry_only(&self) -> Option<MutationResult> {
+        self.role_error(*self.state.is_registry.get(), "registry")
+    }
+
+    /// Sends a lease change to the registry for this chain. The registry decides once it
+    /// handles the message, so `accepted` only means the request went out; read the room
+    /// from the registry to see who holds it.
+    fn room_request(&self, registry_chain_id: String, chain_id: String, action: RoomAction) -> MutationResult {
+        if let Some(error) = self.role_error(!*self.state.is_registry.get(), "non-registry") { return error; }
+        if registry_chain_id.parse::<ChainId>().is_err() {
+            return MutationResult::reject("INVALID_CHAIN_ID", "Invalid registry chain id");
+        }
+        let operation = match action {
+            RoomAction::Claim => Operation::ClaimRoom { registry_chain_id, chain_id },
+            RoomAction::Release => Operation::ReleaseRoom { registry_chain_id, chain_id },
+            RoomAction::Heartbeat => Operation::HeartbeatRoom { registry_chain_id, chain_id },
+        };
+        self.schedule(operation, None)
+    }
+
+    fn host_only(&self) -> Option<MutationResult> {
+        self.role_error(*self.state.is_host.get(), "host")
+    }
+
+    fn player_only(&self) -> Option<MutationResult> {
+        let state = &self.state;
+        self.role_error(!*state.is_host.get() && !*state.is_registry.get() && !*state.is_spectator.get(), "player")
+    }
+
+    /// Hosts seat other chains; player and solo chains only ever seat themselves.
+    fn seats_others(&self) -> bool {
+        *self.state.is_host.get() && !*self.state.is_solo.get()
+    }
+
+    /// Autoplay and strategies each bet every round, so only one may run at a time.
+    fn betting_automatically(&self) -> Option<MutationResult> {
+        let busy = self.state.autoplay.get().as_ref().is_some_and(Autoplay::is_active)
+            || self.state.strategy.get().as_ref().is_some_and(StrategyRun::is_active);
+        busy.then(|| MutationResult::reject("AUTOPLAY_ACTIVE", "Autoplay or a strategy is already running"))
+    }
+
+    /// Dry-runs a limit change on a copy of this chain's settings before scheduling it.
+    /// Only player and solo chains set limits, and only their own.
+    async fn change_limits(&self, change: LimitChange) -> async_graphql::Result<MutationResult> {
+        let state = &self.state;
+        let allowed = !*state.is_registry.get() && !*state.is_spectator.get() && (!*state.is_host.get() || *state.is_solo.get());
+        if let Some(error) = self.role_error(allowed, "player or solo") { return Ok(error); }
+        let chain_id = self.runtime.chain_id().to_string();
+        let mut gaming = state.responsible_gaming.get(&chain_id).await?.unwrap_or_default();
+        let now_ms = self.runtime.system_time().micros() / 1000;
+        match gaming.apply(change.clone(), now_ms) {
+            Ok(()) => Ok(self.schedule(Operation::ChangeLimits { change }, None)),
+            Err(error) => Ok(error.into()),
+        }
+    }
+
+    /// The player a bet would be placed for: `player_id` on the host, this chain's own player otherwise.
+    async fn bettor(&self, player_id: &str) -> async_graphql::Result<Option<Player>> {
+        if self.seats_others() {
+            return Ok(self.state.players.get(player_id).await?);
+        }
+        let Some(my_player) = self.state.my_player.get().clone() else { return Ok(None) };
+        Ok(Some(self.state.players.get(&my_player.chain_id).await?.unwrap_or(my_player)))
+    }
+}
+
 #[Object]
 impl MutationRoot {
-    async fn register_player(&self, player_id: String, name: String, initial_balance: i32) -> String {
-        self.runtime.schedule_operation(&linera_roulette::Operation::RegisterPlayer {
-            player_id: player_id.clone(),
-            name: name.clone(),
-            initial_balance: initial_balance as u64,
-        });
-        format!("Registered {} with balance {}", name, initial_balance)
+    async fn register_player(&self, #[graphql(default)] table_id: TableId, player_id: String, name: String, initial_balance: i32, invite_code: Option<String>) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.role_error(!*self.state.is_registry.get() && !*self.state.is_spectator.get(), "host or player") {
+            return Ok(error);
+        }
+        let Ok(initial_balance) = u64::try_from(initial_balance) else { return Ok(BetError::InvalidAmount.into()) };
+        if *self.state.is_host.get() && self.state.table_configs.get(&table_id).await?.is_none() {
+            return Ok(BetError::UnknownTable.into());
+        }
+        let registered = if self.seats_others() {
+            self.state.players.contains_key(&player_id).await?
+        } else {
+            self.state.my_player.get().is_some()
+        };
+        if registered {
+            return Ok(MutationResult::reject("ALREADY_REGISTERED", "Already registered"));
+        }
+        Ok(self.schedule(Operation::RegisterPlayer { table_id, player_id, name, initial_balance, invite_code }, Some(initial_balance)))
+    }
+
+    async fn place_bet(&self, #[graphql(default)] table_id: TableId, player_id: String, bet_type: BetType, numbers: Vec<i32>, amount: i32) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.role_error(!*self.state.is_registry.get() && !*self.state.is_spectator.get(), "host or player") {
+            return Ok(error);
+        }
+        let Ok(amount) = u64::try_from(amount) else { return Ok(BetError::InvalidAmount.into()) };
+        let Ok(numbers) = numbers.into_iter().map(u8::try_from).collect::<Result<Vec<u8>, _>>() else {
+            return Ok(BetError::InvalidNumbers.into());
+        };
+        let player = self.bettor(&player_id).await?;
+        let game = self.state.tables.get(&table_id).await?;
+        let config = self.state.table_configs.get(&table_id).await?;
+        let new_balance = match validate_bet(player.as_ref(), game.as_ref().zip(config.as_ref()), table_id, bet_type, &numbers, amount) {
+            Ok(new_balance) => new_balance,
+            Err(error) => return Ok(error.into()),
+        };
+        if let Some(player) = &player {
+            let gaming = self.state.responsible_gaming.get(&player.chain_id).await?.unwrap_or_default();
+            if let Err(error) = gaming.check_stake(amount, self.runtime.system_time().micros() / 1000) {
+                return Ok(error.into());
+            }
+        }
+        Ok(self.schedule(Operation::PlaceBet { table_id, player_id, bet_type, numbers, amount }, Some(new_balance)))
+    }
+
+    async fn start_round(&self, #[graphql(default)] table_id: TableId) -> MutationResult {
+        if let Some(error) = self.host_only() { return error; }
+        self.schedule(Operation::StartRound { table_id }, None)
+    }
+
+    async fn spin_wheel(&self, #[graphql(default)] table_id: TableId) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.role_error(!*self.state.is_registry.get() && !*self.state.is_spectator.get(), "host or player") {
+            return Ok(error);
+        }
+        let game = self.state.tables.get(&table_id).await?.unwrap_or_default();
+        if game.is_spinning {
+            return Ok(BetError::WheelSpinning.into());
+        }
+        if *self.state.is_host.get() && game.current_bets.is_empty() {
+            return Ok(MutationResult::reject("NO_BETS", "No bets on the table"));
+        }
+        Ok(self.schedule(Operation::SpinWheel { table_id }, None))
+    }
+
+    async fn create_table(&self, config: TableConfig) -> MutationResult {
+        if let Some(error) = self.host_only() { return error; }
+        self.schedule(Operation::CreateTable { config }, None)
+    }
+
+    async fn configure_table(&self, #[graphql(default)] table_id: TableId, config: TableConfig) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.host_only() { return Ok(error); }
+        if self.state.table_configs.get(&table_id).await?.is_none() {
+            return Ok(BetError::UnknownTable.into());
+        }
+        Ok(self.schedule(Operation::ConfigureTable { table_id, config }, None))
+    }
+
+    async fn tick(&self) -> MutationResult {
+        if let Some(error) = self.host_only() { return error; }
+        self.schedule(Operation::Tick, None)
+    }
+
+    async fn spectate(&self, host_chain_id: String) -> MutationResult {
+        if let Some(error) = self.role_error(*self.state.is_spectator.get(), "spectator") { return error; }
+        self.schedule(Operation::Spectate { host_chain_id }, None)
+    }
+
+    async fn announce_tables(&self) -> MutationResult {
+        if let Some(error) = self.host_only() { return error; }
+        self.schedule(Operation::AnnounceTables, None)
+    }
+
+    async fn join_table(&self, host_chain_id: String, #[graphql(default)] table_id: TableId, invite_code: Option<String>) -> MutationResult {
+        if let Some(error) = self.player_only() { return error; }
+        self.schedule(Operation::JoinTable { host_chain_id, table_id, invite_code }, None)
+    }
+
+    async fn add_room(&self, chain_id: String, name: String) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.registry_only() { return Ok(error); }
+        if chain_id.parse::<ChainId>().is_err() {
+            return Ok(MutationResult::reject("INVALID_CHAIN_ID", "Invalid chain id"));
+        }
+        if self.state.rooms.contains_key(&chain_id).await? {
+            return Ok(MutationResult::reject("ROOM_EXISTS", "Room already registered"));
+        }
+        Ok(self.schedule(Operation::AddRoom { chain_id, name }, None))
+    }
+
+    async fn claim_room(&self, registry_chain_id: String, chain_id: String) -> MutationResult {
+        self.room_request(registry_chain_id, chain_id, RoomAction::Claim)
+    }
+
+    async fn release_room(&self, registry_chain_id: String, chain_id: String) -> MutationResult {
+        self.room_request(registry_chain_id, chain_id, RoomAction::Release)
+    }
+
+    async fn heartbeat_room(&self, registry_chain_id: String, chain_id: String) -> MutationResult {
+        self.room_request(registry_chain_id, chain_id, RoomAction::Heartbeat)
+    }
+
+    async fn enable_registry(&self) -> MutationResult {
+        let state = &self.state;
+        let unassigned = !*state.is_host.get()
+            && !*state.is_registry.get()
+            && !*state.is_spectator.get()
+            && state.host_chain_id.get().is_none()
+            && state.my_player.get().is_none();
+        if let Some(error) = self.role_error(unassigned, "unassigned") { return error; }
+        self.schedule(Operation::EnableRegistry, None)
+    }
+
+    async fn sync_balance(&self) -> MutationResult {
+        if let Some(error) = self.player_only() { return error; }
+        if self.state.host_chain_id.get().is_none() {
+            return MutationResult::reject("NO_HOST", "Not bound to a host chain");
+        }
+        self.schedule(Operation::SyncBalance, None)
+    }
+
+    async fn leave_table(&self) -> MutationResult {
+        if let Some(error) = self.player_only() { return error; }
+        if self.state.my_player.get().is_none() {
+            return BetError::NotRegistered.into();
+        }
+        self.schedule(Operation::LeaveTable, None)
+    }
+
+    /// Checks the first round of the layout like `placeBet` would; later rounds are placed as results arrive.
+    async fn start_autoplay(&self, bets: Vec<BetLayout>, rounds: u32, stop_loss: Option<u64>, take_profit: Option<u64>) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.player_only() { return Ok(error); }
+        if let Some(error) = self.betting_automatically() { return Ok(error); }
+        let Some(mut player) = self.bettor("").await? else { return Ok(BetError::NotRegistered.into()) };
+        if let Err(error) = Autoplay::new(player.table_id, bets.clone(), rounds, stop_loss, take_profit, player.balance) {
+            return Ok(error.into());
+        }
+        let game = self.state.tables.get(&player.table_id).await?;
+        let config = self.state.table_configs.get(&player.table_id).await?;
+        for bet in &bets {
+            match validate_bet(Some(&player), game.as_ref().zip(config.as_ref()), player.table_id, bet.bet_type, &bet.numbers, bet.amount) {
+                Ok(new_balance) => player.balance = new_balance,
+                Err(error) => return Ok(error.into()),
+            }
+        }
+        Ok(self.schedule(Operation::StartAutoplay { bets, rounds, stop_loss, take_profit }, Some(player.balance)))
+    }
+
+    async fn stop_autoplay(&self) -> MutationResult {
+        if let Some(error) = self.player_only() { return error; }
+        if !self.state.autoplay.get().as_ref().is_some_and(Autoplay::is_active) {
+            return MutationResult::reject("NO_AUTOPLAY", "Autoplay is not running");
+        }
+        self.schedule(Operation::StopAutoplay, None)
+    }
+
+    /// Checks the opening bet like `placeBet` would; the strategy sizes every later one.
+    async fn start_strategy(&self, kind: StrategyKind, bet: BetLayout) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.player_only() { return Ok(error); }
+        if let Some(error) = self.betting_automatically() { return Ok(error); }
+        let Some(player) = self.bettor("").await? else { return Ok(BetError::NotRegistered.into()) };
+        if bet.amount == 0 { return Ok(BetError::InvalidAmount.into()); }
+        let game = self.state.tables.get(&player.table_id).await?;
+        let config = self.state.table_configs.get(&player.table_id).await?;
+        let mut opening = StrategyRun::new(player.table_id, kind, bet.clone());
+        let Some(first) = opening.next_round(None, player.balance).pop() else { return Ok(BetError::InsufficientBalance.into()) };
+        match validate_bet(Some(&player), game.as_ref().zip(config.as_ref()), player.table_id, first.bet_type, &first.numbers, first.amount) {
+            Ok(new_balance) => Ok(self.schedule(Operation::StartStrategy { kind, bet }, Some(new_balance))),
+            Err(error) => Ok(error.into()),
+        }
+    }
+
+    async fn stop_strategy(&self) -> MutationResult {
+        if let Some(error) = self.player_only() { return error; }
+        if !self.state.strategy.get().as_ref().is_some_and(StrategyRun::is_active) {
+            return MutationResult::reject("NO_STRATEGY", "No strategy is running");
+        }
+        self.schedule(Operation::StopStrategy, None)
+    }
+
+    /// Tighter limits apply at once; looser ones wait a day.
+    async fn set_limits(&self, limits: PlayerLimits) -> async_graphql::Result<MutationResult> {
+        self.change_limits(LimitChange::SetLimits(limits)).await
+    }
+
+    /// Blocks betting for `durationMs`; a cool-off can be extended but not shortened.
+    async fn cool_off(&self, duration_ms: u64) -> async_graphql::Result<MutationResult> {
+        self.change_limits(LimitChange::CoolOff { duration_ms }).await
+    }
+
+    /// Blocks betting until `untilMs`; an exclusion can be extended but not lifted early.
+    async fn self_exclude(&self, until_ms: u64) -> async_graphql::Result<MutationResult> {
+        self.change_limits(LimitChange::SelfExclude { until_ms }).await
+    }
+
+    async fn reset_table(&self, #[graphql(default)] table_id: TableId, #[graphql(default)] notify_players: bool) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.host_only() { return Ok(error); }
+        if self.state.table_configs.get(&table_id).await?.is_none() {
+            return Ok(BetError::UnknownTable.into());
+        }
+        Ok(self.schedule(Operation::ResetTable { table_id, notify_players }, None))
     }
 
-    async fn place_bet(&self, player_id: String, bet_type: linera_roulette::BetType, numbers: Vec<i32>, amount: i32) -> String {
-        self.runtime.schedule_operation(&linera_roulette::Operation::PlaceBet {
-            player_id,
-            bet_type,
-            numbers: numbers.into_iter().map(|n| n as u8).collect(),
-            amount: amount as u64,
-        });
-        format!("Bet placed: {:?} amount {}", bet_type, amount)
+    async fn kick_player(&self, chain_id: String) -> async_graphql::Result<MutationResult> {
+        if let Some(error) = self.host_only() { return Ok(error); }
+        if !self.state.players.contains_key(&chain_id).await? {
+            return Ok(BetError::NotRegistered.into());
+        }
+        Ok(self.schedule(Operation::KickPlayer { chain_id }, None))
     }
 
-    async fn start_round(&self) -> String {
-        self.runtime.schedule_operation(&linera_roulette::Operation::StartRound);
-        "Round started".to_string()
+    async fn add_table_member(&self, #[graphql(default)] table_id: TableId, chain_id: String) -> MutationResult {
+        if let Some(error) = self.host_only() { return error; }
+        self.schedule(Operation::AddTableMember { table_id, chain_id }, None)
     }
 
-    async fn spin_wheel(&self) -> String {
-        self.runtime.schedule_operation(&linera_roulette::Operation::SpinWheel);
-        "Spinning".to_string()
+    async fn remove_table_member(&self, #[graphql(default)] table_id: TableId, chain_id: String) -> MutationResult {
+        if let Some(error) = self.host_only() { return error; }
+        self.schedule(Operation::RemoveTableMember { table_id, chain_id }, None)
     }
 }
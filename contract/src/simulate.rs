@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prints a house-edge report for every bet type on both wheels, or for one bet
+//! layout given with `--bet`.
+//!
+//! `cargo run --release --bin roulette_simulate -- [--sessions N] [--spins N] [--bankroll N] [--seed N] [--bet TYPE[:NUMBERS]:AMOUNT]...`
+
+use linera_roulette::{
+    preview_outcomes,
+    simulation::{house_edge_report, layout_bounds, simulate, Rng, SimulationConfig, SimulationReport},
+    BetLayout, BetType, WheelVariant, DOUBLE_ZERO,
+};
+
+const USAGE: &str = "usage: roulette_simulate [--sessions N] [--spins N] [--bankroll N] [--seed N] [--bet TYPE[:NUMBERS]:AMOUNT]...
+  --bet may be repeated; the bets are played together every spin, e.g.
+  --bet red:5 --bet straight:17:1 --bet first_dozen:2 --bet straight:0,32:1";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (config, seed, layout) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(error) => return usage(&error),
+    };
+
+    for variant in [WheelVariant::SingleZero, WheelVariant::DoubleZero] {
+        let config = SimulationConfig { variant, ..config.clone() };
+        if layout.is_empty() {
+            print_house_edge_report(&config, seed);
+        } else if let Err(error) = print_layout_report(&layout, &config, seed) {
+            eprintln!("{:?}: {}", variant, error);
+        }
+        println!();
+    }
+}
+
+fn print_house_edge_report(config: &SimulationConfig, seed: u64) {
+    println!(
+        "{:?}: {} sessions x {} spins, bankroll {} chips, one chip per spin",
+        config.variant, config.sessions, config.spins_per_session, config.bankroll
+    );
+    print_header("bet");
+    for (bet_type, report) in house_edge_report(config, seed) {
+        print_row(&format!("{:?}", bet_type), &report);
+    }
+}
+
+fn print_layout_report(layout: &[BetLayout], config: &SimulationConfig, seed: u64) -> Result<(), String> {
+    if let Some(bet) = layout.iter().find(|b| b.numbers.iter().any(|n| !config.variant.is_valid_pocket(*n))) {
+        return Err(format!("{:?} has a number that is not on this wheel", bet.bet_type));
+    }
+    let preview = preview_outcomes(layout, config.variant).map_err(|error| error.to_string())?;
+    println!(
+        "{:?}: {} sessions x {} spins, bankroll {} chips, {} chips per spin",
+        config.variant, config.sessions, config.spins_per_session, config.bankroll, preview.total_staked
+    );
+    print_header("layout");
+    let report = simulate(layout, config, &mut Rng::new(seed)).map_err(|error| error.to_string())?;
+    print_row("simulated", &report);
+    let exact_rtp = 1.0 + preview.expected_value / preview.total_staked as f64;
+    println!("{:<14} {:>12} {:>8.3}% {:>10.3}%", "exact", "", exact_rtp * 100.0, (1.0 - exact_rtp) * 100.0);
+    Ok(())
+}
+
+fn print_header(label: &str) {
+    println!("{:<14} {:>12} {:>9} {:>11} {:>10} {:>14}", label, "spins", "RTP", "house edge", "variance", "risk of ruin");
+}
+
+fn print_row(label: &str, report: &SimulationReport) {
+    println!(
+        "{:<14} {:>12} {:>8.3}% {:>10.3}% {:>10.3} {:>13.2}%",
+        label,
+        report.spins,
+        report.rtp * 100.0,
+        report.house_edge * 100.0,
+        report.variance,
+        report.risk_of_ruin * 100.0
+    );
+}
+
+/// Reads the options into a config, a seed and a bet layout whose stake and
+/// payouts fit in a `u64`.
+fn parse_args(args: &[String]) -> Result<(SimulationConfig, u64, Vec<BetLayout>), String> {
+    let mut config = SimulationConfig::default();
+    let mut seed = 0;
+    let mut layout = Vec::new();
+    for pair in args.chunks(2) {
+        let Some(value) = pair.get(1) else { return Err(format!("{} needs a value", pair[0])) };
+        if pair[0] == "--bet" {
+            layout.push(parse_bet(value).map_err(|error| format!("--bet {}: {}", value, error))?);
+            continue;
+        }
+        let Ok(value) = value.parse::<u64>() else { return Err(format!("{} needs a number", pair[0])) };
+        match pair[0].as_str() {
+            "--sessions" => config.sessions = value,
+            "--spins" => config.spins_per_session = value,
+            "--bankroll" => config.bankroll = value,
+            "--seed" => seed = value,
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+    if layout_bounds(&layout).is_err() {
+        return Err("the bets' total stake or payout is too large".to_string());
+    }
+    Ok((config, seed, layout))
+}
+
+/// Parses `TYPE:AMOUNT` or `TYPE:NUMBERS:AMOUNT`, where `NUMBERS` is comma-separated
+/// (`00` for double zero) and `TYPE` is a bet type in any case, with or without underscores.
+fn parse_bet(spec: &str) -> Result<BetLayout, String> {
+    let parts: Vec<&str> = spec.split(':').collect();
+    let (name, numbers, amount) = match parts[..] {
+        [name, amount] => (name, None, amount),
+        [name, numbers, amount] => (name, Some(numbers), amount),
+        _ => return Err("expected TYPE:AMOUNT or TYPE:NUMBERS:AMOUNT".to_string()),
+    };
+    let normalized = name.replace(['_', '-'], "").to_lowercase();
+    let bet_type = BetType::ALL
+        .into_iter()
+        .find(|bet_type| format!("{:?}", bet_type).to_lowercase() == normalized)
+        .ok_or_else(|| format!("unknown bet type {}", name))?;
+    let numbers = match numbers {
+        Some(numbers) => numbers
+            .split(',')
+            .map(|n| match n.trim() {
+                "00" => Ok(DOUBLE_ZERO),
+                n => n.parse::<u8>().map_err(|_| format!("{} is not a pocket number", n)),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => vec![],
+    };
+    if bet_type == BetType::Straight && numbers.is_empty() {
+        return Err("a straight bet needs a number".to_string());
+    }
+    let amount = amount.parse::<u64>().ok().filter(|&amount| amount > 0).ok_or_else(|| format!("{} is not a positive amount", amount))?;
+    Ok(BetLayout { bet_type, numbers, amount })
+}
+
+fn usage(error: &str) {
+    eprintln!("error: {}", error);
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Monte Carlo checks of the payout tables, run off-chain against the same
+//! `BetType` rules the contract settles with.
+
+use crate::{BetError, BetLayout, BetType, WheelVariant};
+
+/// SplitMix64: small, seedable and good enough for spin statistics. Not for on-chain use.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly random pocket of `variant`, without modulo bias.
+    pub fn pocket(&mut self, variant: WheelVariant) -> u8 {
+        let count = variant.pocket_count() as u64;
+        let zone = u64::MAX - u64::MAX % count;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % count) as u8;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub variant: WheelVariant,
+    /// Independent sessions, each starting from `bankroll`.
+    pub sessions: u64,
+    pub spins_per_session: u64,
+    pub bankroll: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self { variant: WheelVariant::SingleZero, sessions: 1_000, spins_per_session: 1_000, bankroll: 100 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    pub variant: WheelVariant,
+    pub spins: u64,
+    pub total_staked: u128,
+    pub total_returned: u128,
+    /// Share of staked chips paid back.
+    pub rtp: f64,
+    pub house_edge: f64,
+    /// Variance of the net result per spin, in chips.
+    pub variance: f64,
+    /// Share of sessions that ran out of chips before their last spin.
+    pub risk_of_ruin: f64,
+}
+
+/// Chips `layout` stakes per spin and the most it can return on one spin, or
+/// `InvalidAmount` if either does not fit in a `u64`.
+pub fn layout_bounds(layout: &[BetLayout]) -> Result<(u64, u64), BetError> {
+    let stake = layout.iter().try_fold(0u64, |sum, b| sum.checked_add(b.amount));
+    let max_return = layout.iter().try_fold(0u64, |sum, b| sum.checked_add(b.bet_type.checked_payout(b.amount)?));
+    stake.zip(max_return).ok_or(BetError::InvalidAmount)
+}
+
+/// Plays `layout` on every spin of every session until the spins run out or the
+/// bankroll can no longer cover the layout. Fails like `layout_bounds`.
+pub fn simulate(layout: &[BetLayout], config: &SimulationConfig, rng: &mut Rng) -> Result<SimulationReport, BetError> {
+    let (stake, _) = layout_bounds(layout)?;
+    let mut spins = 0u64;
+    let mut total_returned = 0u128;
+    let mut ruined = 0u64;
+    // Welford's running mean and sum of squared deviations of the net per spin.
+    let mut mean = 0.0;
+    let mut m2 = 0.0;
+
+    for _ in 0..config.sessions {
+        let mut balance = config.bankroll;
+        for _ in 0..config.spins_per_session {
+            let Some(staked) = balance.checked_sub(stake) else {
+                ruined += 1;
+                break;
+            };
+            let number = rng.pocket(config.variant);
+            // Bounded by `layout_bounds`, so the sum cannot overflow.
+            let returned = layout
+                .iter()
+                .filter(|b| b.bet_type.is_winner(number, &b.numbers))
+                .filter_map(|b| b.bet_type.checked_payout(b.amount))
+                .fold(0u64, u64::saturating_add);
+            // A bankroll already near `u64::MAX` can hold no more; it stays solvent either way.
+            balance = staked.saturating_add(returned);
+            total_returned += returned as u128;
+            spins += 1;
+
+            let net = returned as f64 - stake as f64;
+            let delta = net - mean;
+            mean += delta / spins as f64;
+            m2 += delta * (net - mean);
+        }
+    }
+
+    let total_staked = stake as u128 * spins as u128;
+    let rtp = if total_staked == 0 { 0.0 } else { total_returned as f64 / total_staked as f64 };
+    Ok(SimulationReport {
+        variant: config.variant,
+        spins,
+        total_staked,
+        total_returned,
+        rtp,
+        house_edge: 1.0 - rtp,
+        variance: if spins > 1 { m2 / (spins - 1) as f64 } else { 0.0 },
+        risk_of_ruin: if config.sessions == 0 { 0.0 } else { ruined as f64 / config.sessions as f64 },
+    })
+}
+
+/// A one-chip bet of `bet_type`; straight bets go on 17.
+pub fn unit_bet(bet_type: BetType) -> BetLayout {
+    let numbers = if bet_type == BetType::Straight { vec![17] } else { vec![] };
+    BetLayout { bet_type, numbers, amount: 1 }
+}
+
+/// One report per bet type, each from a one-chip bet played on its own.
+pub fn house_edge_report(config: &SimulationConfig, seed: u64) -> Vec<(BetType, SimulationReport)> {
+    let mut rng = Rng::new(seed);
+    BetType::ALL
+        .iter()
+        .map(|&bet_type| (bet_type, simulate(&[unit_bet(bet_type)], config, &mut rng).expect("a one-chip bet always fits")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview_outcomes;
+
+    fn config(variant: WheelVariant) -> SimulationConfig {
+        SimulationConfig { variant, sessions: 200, spins_per_session: 1_000, bankroll: 1_000_000 }
+    }
+
+    #[test]
+    fn test_pockets_cover_the_wheel() {
+        let mut rng = Rng::new(7);
+        for variant in [WheelVariant::SingleZero, WheelVariant::DoubleZero] {
+            let mut seen = vec![0u32; variant.pocket_count() as usize];
+            for _ in 0..100_000 {
+                seen[rng.pocket(variant) as usize] += 1;
+            }
+            assert!(seen.iter().all(|&count| count > 0), "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn test_rtp_converges_to_exact_edge() {
+        for variant in [WheelVariant::SingleZero, WheelVariant::DoubleZero] {
+            for (bet_type, report) in house_edge_report(&config(variant), 42) {
+                let exact = 1.0 + preview_outcomes(&[unit_bet(bet_type)], variant).unwrap().expected_value;
+                // Straight bets are the noisiest: one chip wins 36, so allow for it.
+                let tolerance = if bet_type == BetType::Straight { 0.05 } else { 0.01 };
+                assert!((report.rtp - exact).abs() < tolerance, "{:?} {:?}: {} vs {}", variant, bet_type, report.rtp, exact);
+            }
+        }
+    }
+
+    #[test]
+    fn test_double_zero_has_larger_edge() {
+        let single = simulate(&[unit_bet(BetType::Red)], &config(WheelVariant::SingleZero), &mut Rng::new(1)).unwrap();
+        let double = simulate(&[unit_bet(BetType::Red)], &config(WheelVariant::DoubleZero), &mut Rng::new(1)).unwrap();
+        assert!(double.house_edge > single.house_edge);
+    }
+
+    #[test]
+    fn test_variance_follows_payout_odds() {
+        let red = simulate(&[unit_bet(BetType::Red)], &config(WheelVariant::SingleZero), &mut Rng::new(3)).unwrap();
+        let straight = simulate(&[unit_bet(BetType::Straight)], &config(WheelVariant::SingleZero), &mut Rng::new(3)).unwrap();
+        // Even money: net is +-1, so the variance is just under 1; a straight bet's is about 35.
+        assert!((red.variance - 1.0).abs() < 0.05);
+        assert!(straight.variance > 30.0);
+    }
+
+    #[test]
+    fn test_small_bankroll_is_ruined() {
+        let config = SimulationConfig { variant: WheelVariant::DoubleZero, sessions: 100, spins_per_session: 10_000, bankroll: 10 };
+        let report = simulate(&[unit_bet(BetType::Red)], &config, &mut Rng::new(9)).unwrap();
+        assert!(report.risk_of_ruin > 0.9);
+        assert!(report.spins < config.sessions * config.spins_per_session);
+
+        let flush = SimulationConfig { bankroll: 1_000_000, ..config };
+        assert_eq!(simulate(&[unit_bet(BetType::Red)], &flush, &mut Rng::new(9)).unwrap().risk_of_ruin, 0.0);
+    }
+
+    #[test]
+    fn test_bankroll_at_u64_max_does_not_overflow() {
+        let config = SimulationConfig { variant: WheelVariant::SingleZero, sessions: 2, spins_per_session: 100, bankroll: u64::MAX };
+        let report = simulate(&[unit_bet(BetType::Red)], &config, &mut Rng::new(5)).unwrap();
+        assert_eq!(report.spins, 200);
+        assert_eq!(report.total_staked, 200);
+        assert_eq!(report.risk_of_ruin, 0.0);
+        assert!(report.rtp > 0.0 && report.rtp <= 2.0);
+    }
+
+    #[test]
+    fn test_unrepresentable_layout_is_rejected() {
+        let huge = |bet_type, amount| BetLayout { bet_type, numbers: vec![17], amount };
+        let config = config(WheelVariant::SingleZero);
+        assert_eq!(layout_bounds(&[huge(BetType::Red, u64::MAX), huge(BetType::Black, 1)]), Err(BetError::InvalidAmount));
+        assert_eq!(layout_bounds(&[huge(BetType::Straight, u64::MAX / 2)]), Err(BetError::InvalidAmount));
+        assert!(simulate(&[huge(BetType::Red, u64::MAX)], &config, &mut Rng::new(1)).is_err());
+        assert_eq!(layout_bounds(&[huge(BetType::Straight, u64::MAX / 36)]), Ok((u64::MAX / 36, u64::MAX / 36 * 36)));
+    }
+}
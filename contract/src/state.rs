@@ -1,15 +1,82 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, SetView, ViewStorageContext};
-use linera_roulette::{GameState, Player};
+use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, SetView, View, ViewError, ViewStorageContext};
+use linera_roulette::{SCHEMA_VERSION, limits::ResponsibleGaming, strategy::StrategyRun, Autoplay, GameState, GameStateV1, PlayerV1, HostListing, PendingJoin, PendingRequest, Player, Room, RequestError, RequestId, TableConfig, TableId, Undelivered};
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct RouletteState {
-    pub game: RegisterView<GameState>,
+    pub tables: MapView<TableId, GameState>,
+    pub table_configs: MapView<TableId, TableConfig>,
+    pub next_table_id: RegisterView<TableId>,
     pub players: MapView<String, Player>,
     pub is_host: RegisterView<bool>,
     pub host_chain_id: RegisterView<Option<String>>,
     pub my_player: RegisterView<Option<Player>>,
+    pub wallet_balance: RegisterView<u64>,
     pub player_chains: SetView<String>,
+    pub registry_chain_id: RegisterView<Option<String>>,
+    pub pending_join: RegisterView<Option<PendingJoin>>,
+    pub is_registry: RegisterView<bool>,
+    pub is_spectator: RegisterView<bool>,
+    pub listings: MapView<String, HostListing>,
+    pub pending_requests: MapView<RequestId, PendingRequest>,
+    pub next_request_id: RegisterView<RequestId>,
+    pub last_errors: RegisterView<Vec<RequestError>>,
+    pub undelivered: MapView<String, Undelivered>,
+    pub player_nonces: MapView<String, RequestId>,
+    pub balance_is_stale: RegisterView<bool>,
+    pub is_solo: RegisterView<bool>,
+    pub rooms: MapView<String, Room>,
+    pub table_sessions: MapView<TableId, u64>,
+    /// Balances left behind by table resets that did not pay players out.
+    pub archived_balances: MapView<String, u64>,
+    /// `SCHEMA_VERSION` of the layout above; 0 until a chain is first stamped.
+    pub schema_version: RegisterView<u32>,
+    /// Player chains only. Appended after `schema_version` so earlier fields keep their keys.
+    pub autoplay: RegisterView<Option<Autoplay>>,
+    pub strategy: RegisterView<Option<StrategyRun>>,
+    /// Host: every player's settings, kept after they leave. Player: this chain's own, as last confirmed.
+    pub responsible_gaming: MapView<String, ResponsibleGaming>,
+    /// Host: stakes kept from players who left mid-round under `LeavePolicy::Forfeit`.
+    pub house_funds: RegisterView<u64>,
+}
+
+/// The schema version 1 layout, loaded only to migrate it.
+#[derive(RootView)]
+#[view(context = ViewStorageContext)]
+pub struct RouletteStateV1 {
+    pub game: RegisterView<GameStateV1>,
+    pub players: MapView<String, PlayerV1>,
+    pub is_host: RegisterView<bool>,
+    pub host_chain_id: RegisterView<Option<String>>,
+    pub my_player: RegisterView<Option<PlayerV1>>,
+    pub player_chains: SetView<String>,
+}
+
+impl RouletteState {
+    /// Loads the state, or the version 1 state if the chain is still in that layout.
+    /// Version 1 registers sit where the current layout expects other types, so only a
+    /// state that fails to decode is read as version 1. Any other error panics rather
+    /// than letting a storage failure pass for an old layout.
+    pub async fn load_current(context: ViewStorageContext) -> Result<Self, RouletteStateV1> {
+        match RouletteState::load(context.clone()).await {
+            Ok(state) => {
+                state.layout_version();
+                Ok(state)
+            }
+            Err(ViewError::BcsError(_)) => Err(RouletteStateV1::load(context).await.expect("Failed to load version 1 state")),
+            Err(error) => panic!("Failed to load state: {error}"),
+        }
+    }
+
+    /// The layout version this state is in. Unstamped chains were written in the version 2
+    /// layout before versions were recorded. Panics on a version this build cannot read.
+    pub fn layout_version(&self) -> u32 {
+        match *self.schema_version.get() {
+            0 => 2,
+            version if (2..=SCHEMA_VERSION).contains(&version) => version,
+            version => panic!("Unsupported state schema version {version}"),
+        }
+    }
 }
@@ -0,0 +1,303 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Classic progression strategies. Each one stakes multiples of a base bet on a
+//! single position and only changes the stake between rounds, so they are plain
+//! state machines that off-chain tools can drive too.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BetLayout, SpinResult, TableId};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum StrategyStop {
+    /// Labouchère crossed off its whole line.
+    TargetReached,
+    InsufficientBalance,
+    BetRejected,
+    /// The player left, was kicked, or the table was reset.
+    Unseated,
+    Stopped,
+}
+
+pub trait BettingStrategy {
+    /// The bets for the next round. `last_result` is the spin that settled the
+    /// previous bets, `None` before the first round.
+    fn next_bets(&mut self, last_result: Option<&SpinResult>, balance: u64) -> Result<Vec<BetLayout>, StrategyStop>;
+}
+
+fn won(bet: &BetLayout, result: &SpinResult) -> bool {
+    bet.bet_type.is_winner(result.number, &bet.numbers)
+}
+
+/// `units` base bets, if the balance covers them.
+fn stake(bet: &BetLayout, units: u64, balance: u64) -> Result<Vec<BetLayout>, StrategyStop> {
+    match bet.amount.checked_mul(units) {
+        Some(amount) if amount <= balance => Ok(vec![BetLayout { amount, ..bet.clone() }]),
+        _ => Err(StrategyStop::InsufficientBalance),
+    }
+}
+
+/// Doubles the stake after every loss and drops back to the base bet after a win.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Martingale {
+    pub bet: BetLayout,
+    pub losses: u32,
+}
+
+impl BettingStrategy for Martingale {
+    fn next_bets(&mut self, last_result: Option<&SpinResult>, balance: u64) -> Result<Vec<BetLayout>, StrategyStop> {
+        if let Some(result) = last_result {
+            self.losses = if won(&self.bet, result) { 0 } else { self.losses + 1 };
+        }
+        let units = 2u64.checked_pow(self.losses).ok_or(StrategyStop::InsufficientBalance)?;
+        stake(&self.bet, units, balance)
+    }
+}
+
+/// Adds one base bet after a loss and takes one away after a win, never going below one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DAlembert {
+    pub bet: BetLayout,
+    pub units: u64,
+}
+
+impl BettingStrategy for DAlembert {
+    fn next_bets(&mut self, last_result: Option<&SpinResult>, balance: u64) -> Result<Vec<BetLayout>, StrategyStop> {
+        if let Some(result) = last_result {
+            self.units = if won(&self.bet, result) { self.units.saturating_sub(1).max(1) } else { self.units + 1 };
+        }
+        stake(&self.bet, self.units, balance)
+    }
+}
+
+/// Steps one place along 1, 1, 2, 3, 5, ... after a loss and two places back after a win.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Fibonacci {
+    pub bet: BetLayout,
+    pub step: u32,
+}
+
+impl Fibonacci {
+    fn units(step: u32) -> Option<u64> {
+        let (mut a, mut b) = (1u64, 1u64);
+        for _ in 0..step {
+            (a, b) = (b, a.checked_add(b)?);
+        }
+        Some(a)
+    }
+}
+
+impl BettingStrategy for Fibonacci {
+    fn next_bets(&mut self, last_result: Option<&SpinResult>, balance: u64) -> Result<Vec<BetLayout>, StrategyStop> {
+        if let Some(result) = last_result {
+            self.step = if won(&self.bet, result) { self.step.saturating_sub(2) } else { self.step + 1 };
+        }
+        let units = Self::units(self.step).ok_or(StrategyStop::InsufficientBalance)?;
+        stake(&self.bet, units, balance)
+    }
+}
+
+/// Stakes the first plus the last number of the line. A win crosses both off, a
+/// loss appends the lost stake; the run ends when the line is empty, having won
+/// its original sum.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Labouchere {
+    pub bet: BetLayout,
+    /// In base bets.
+    pub line: Vec<u64>,
+}
+
+impl Labouchere {
+    pub const DEFAULT_LINE: [u64; 4] = [1, 2, 3, 4];
+
+    fn units(&self) -> Option<u64> {
+        match self.line.as_slice() {
+            [] => None,
+            [only] => Some(*only),
+            [first, .., last] => Some(first + last),
+        }
+    }
+}
+
+impl BettingStrategy for Labouchere {
+    fn next_bets(&mut self, last_result: Option<&SpinResult>, balance: u64) -> Result<Vec<BetLayout>, StrategyStop> {
+        if let (Some(result), Some(units)) = (last_result, self.units()) {
+            if won(&self.bet, result) {
+                self.line.remove(0);
+                self.line.pop();
+            } else {
+                self.line.push(units);
+            }
+        }
+        let units = self.units().ok_or(StrategyStop::TargetReached)?;
+        stake(&self.bet, units, balance)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum StrategyKind {
+    Martingale,
+    DAlembert,
+    Fibonacci,
+    Labouchere,
+}
+
+/// Any of the built-in strategies, as stored on a player chain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Strategy {
+    Martingale(Martingale),
+    DAlembert(DAlembert),
+    Fibonacci(Fibonacci),
+    Labouchere(Labouchere),
+}
+
+impl Strategy {
+    /// A fresh strategy whose base bet is `bet`; Labouchère starts from its default line.
+    pub fn new(kind: StrategyKind, bet: BetLayout) -> Self {
+        match kind {
+            StrategyKind::Martingale => Strategy::Martingale(Martingale { bet, losses: 0 }),
+            StrategyKind::DAlembert => Strategy::DAlembert(DAlembert { bet, units: 1 }),
+            StrategyKind::Fibonacci => Strategy::Fibonacci(Fibonacci { bet, step: 0 }),
+            StrategyKind::Labouchere => Strategy::Labouchere(Labouchere { bet, line: Labouchere::DEFAULT_LINE.to_vec() }),
+        }
+    }
+
+    pub fn kind(&self) -> StrategyKind {
+        match self {
+            Strategy::Martingale(_) => StrategyKind::Martingale,
+            Strategy::DAlembert(_) => StrategyKind::DAlembert,
+            Strategy::Fibonacci(_) => StrategyKind::Fibonacci,
+            Strategy::Labouchere(_) => StrategyKind::Labouchere,
+        }
+    }
+}
+
+impl BettingStrategy for Strategy {
+    fn next_bets(&mut self, last_result: Option<&SpinResult>, balance: u64) -> Result<Vec<BetLayout>, StrategyStop> {
+        match self {
+            Strategy::Martingale(strategy) => strategy.next_bets(last_result, balance),
+            Strategy::DAlembert(strategy) => strategy.next_bets(last_result, balance),
+            Strategy::Fibonacci(strategy) => strategy.next_bets(last_result, balance),
+            Strategy::Labouchere(strategy) => strategy.next_bets(last_result, balance),
+        }
+    }
+}
+
+/// A strategy running on a player chain.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct StrategyRun {
+    pub table_id: TableId,
+    pub kind: StrategyKind,
+    #[graphql(skip)]
+    pub strategy: Strategy,
+    pub rounds_played: u32,
+    /// The bets most recently placed.
+    pub last_bets: Vec<BetLayout>,
+    /// Why the run ended; `None` while it is running.
+    pub stopped: Option<StrategyStop>,
+}
+
+impl StrategyRun {
+    pub fn new(table_id: TableId, kind: StrategyKind, bet: BetLayout) -> Self {
+        Self { table_id, kind, strategy: Strategy::new(kind, bet), rounds_played: 0, last_bets: Vec::new(), stopped: None }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.stopped.is_none()
+    }
+
+    pub fn stop(&mut self, reason: StrategyStop) {
+        if self.is_active() { self.stopped = Some(reason); }
+    }
+
+    /// Asks the strategy for the next round's bets, stopping the run if it has none.
+    pub fn next_round(&mut self, last_result: Option<&SpinResult>, balance: u64) -> Vec<BetLayout> {
+        if !self.is_active() { return Vec::new(); }
+        if last_result.is_some() { self.rounds_played += 1; }
+        match self.strategy.next_bets(last_result, balance) {
+            Ok(bets) => {
+                self.last_bets = bets.clone();
+                bets
+            }
+            Err(reason) => {
+                self.stop(reason);
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_color, BetType, DEFAULT_TABLE_ID};
+
+    fn red(amount: u64) -> BetLayout {
+        BetLayout { bet_type: BetType::Red, numbers: vec![], amount }
+    }
+
+    fn spin(number: u8) -> SpinResult {
+        SpinResult { number, color: get_color(number), timestamp: String::new(), winners: vec![] }
+    }
+
+    const WIN: u8 = 1;
+    const LOSS: u8 = 2;
+
+    /// Stakes placed by `strategy` over `outcomes`, starting with the opening bet.
+    fn stakes(strategy: &mut impl BettingStrategy, outcomes: &[u8]) -> Vec<u64> {
+        let mut stakes = vec![strategy.next_bets(None, 1_000).unwrap()[0].amount];
+        for &number in outcomes {
+            stakes.push(strategy.next_bets(Some(&spin(number)), 1_000).unwrap()[0].amount);
+        }
+        stakes
+    }
+
+    #[test]
+    fn test_martingale_doubles_until_a_win() {
+        let mut strategy = Strategy::new(StrategyKind::Martingale, red(5));
+        assert_eq!(stakes(&mut strategy, &[LOSS, LOSS, LOSS, WIN, LOSS]), vec![5, 10, 20, 40, 5, 10]);
+    }
+
+    #[test]
+    fn test_dalembert_moves_one_unit() {
+        let mut strategy = Strategy::new(StrategyKind::DAlembert, red(5));
+        assert_eq!(stakes(&mut strategy, &[LOSS, LOSS, WIN, WIN, WIN]), vec![5, 10, 15, 10, 5, 5]);
+    }
+
+    #[test]
+    fn test_fibonacci_steps_back_two_on_a_win() {
+        let mut strategy = Strategy::new(StrategyKind::Fibonacci, red(1));
+        assert_eq!(stakes(&mut strategy, &[LOSS, LOSS, LOSS, LOSS, WIN, WIN, WIN]), vec![1, 1, 2, 3, 5, 2, 1, 1]);
+    }
+
+    #[test]
+    fn test_labouchere_finishes_its_line() {
+        let mut strategy = Strategy::new(StrategyKind::Labouchere, red(1));
+        // Line 1 2 3 4: stake 5, lose -> 1 2 3 4 5, stake 6, win -> 2 3 4, stake 6, win -> 3, stake 3.
+        assert_eq!(stakes(&mut strategy, &[LOSS, WIN, WIN]), vec![5, 6, 6, 3]);
+        assert_eq!(strategy.next_bets(Some(&spin(WIN)), 1_000), Err(StrategyStop::TargetReached));
+    }
+
+    #[test]
+    fn test_strategy_stops_when_stake_is_unaffordable() {
+        let mut run = StrategyRun::new(DEFAULT_TABLE_ID, StrategyKind::Martingale, red(10));
+        assert_eq!(run.next_round(None, 35), vec![red(10)]);
+        assert_eq!(run.next_round(Some(&spin(LOSS)), 25), vec![red(20)]);
+        assert!(run.next_round(Some(&spin(LOSS)), 5).is_empty());
+        assert_eq!(run.stopped, Some(StrategyStop::InsufficientBalance));
+        assert_eq!(run.rounds_played, 2);
+        assert!(run.next_round(Some(&spin(WIN)), 1_000).is_empty());
+        assert_eq!(run.rounds_played, 2);
+    }
+
+    #[test]
+    fn test_strategy_state_round_trips() {
+        let mut strategy = Strategy::new(StrategyKind::Labouchere, red(2));
+        strategy.next_bets(None, 100).unwrap();
+        strategy.next_bets(Some(&spin(LOSS)), 100).unwrap();
+        let decoded: Strategy = bcs::from_bytes(&bcs::to_bytes(&strategy).unwrap()).unwrap();
+        assert_eq!(decoded, strategy);
+        assert_eq!(decoded.kind(), StrategyKind::Labouchere);
+    }
+}
@@ -0,0 +1,572 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! End-to-end host and player flows on a local test validator.
+//!
+//! Run with `cargo test --features integration`.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_roulette::{limits::{LimitChange, PlayerLimits}, strategy::StrategyKind, BetLayout, BetType, InstantiationArgument, LeavePolicy, Operation, RouletteAbi, TableConfig, TableMode, DEFAULT_TABLE_ID};
+use linera_sdk::{
+    linera_base_types::ApplicationId,
+    serde_json::Value,
+    test::{ActiveChain, MessageAction, TestValidator},
+};
+
+const INITIAL_BALANCE: u64 = 100;
+
+struct Table {
+    validator: TestValidator,
+    app_id: ApplicationId<RouletteAbi>,
+    host: ActiveChain,
+}
+
+impl Table {
+    /// A host chain with the default table open.
+    async fn open() -> Self {
+        let (validator, app_id, host) =
+            TestValidator::with_current_application::<RouletteAbi, (), InstantiationArgument>((), InstantiationArgument::default()).await;
+        Table { validator, app_id, host }
+    }
+
+    /// A new player chain bound to the host and seated at the default table.
+    async fn seat_player(&self, name: &str) -> ActiveChain {
+        let player = self.validator.new_chain().await;
+        let host_chain_id = self.host.id().to_string();
+        self.operate(&player, Operation::JoinTable { host_chain_id, table_id: DEFAULT_TABLE_ID, invite_code: None }).await;
+        self.operate(&player, Operation::RegisterPlayer {
+            table_id: DEFAULT_TABLE_ID,
+            player_id: player.id().to_string(),
+            name: name.to_string(),
+            initial_balance: INITIAL_BALANCE,
+            invite_code: None,
+        }).await;
+        self.host.handle_received_messages().await;
+        player.handle_received_messages().await;
+        player
+    }
+
+    async fn operate(&self, chain: &ActiveChain, operation: Operation) {
+        chain.add_block(|block| { block.with_operation(self.app_id, operation); }).await;
+    }
+
+    async fn bet(&self, player: &ActiveChain, bet_type: BetType, amount: u64) {
+        self.operate(player, Self::bet_operation(player, bet_type, amount)).await;
+    }
+
+    fn bet_operation(player: &ActiveChain, bet_type: BetType, amount: u64) -> Operation {
+        Operation::PlaceBet { table_id: DEFAULT_TABLE_ID, player_id: player.id().to_string(), bet_type, numbers: vec![], amount }
+    }
+
+    /// Runs `operation` on `player`, lets the host answer it, and has the player reject the
+    /// answer so that it bounces back to the host.
+    async fn reject_reply(&self, player: &ActiveChain, operation: Operation) {
+        let request = player.add_block(|block| { block.with_operation(self.app_id, operation); }).await;
+        let reply = self.host.add_block(|block| { block.with_messages_from(&request); }).await;
+        player.add_block(|block| { block.with_messages_from_by_action(&reply, MessageAction::Reject); }).await;
+        self.host.handle_received_messages().await;
+    }
+
+    /// Runs `operation` on the host and has `player` reject what it was sent.
+    async fn reject_from_host(&self, player: &ActiveChain, operation: Operation) {
+        let sent = self.host.add_block(|block| { block.with_operation(self.app_id, operation); }).await;
+        player.add_block(|block| { block.with_messages_from_by_action(&sent, MessageAction::Reject); }).await;
+        self.host.handle_received_messages().await;
+    }
+
+    async fn query(&self, chain: &ActiveChain, query: &str) -> Value {
+        chain.graphql_query(self.app_id, query).await.response
+    }
+
+    async fn host_balance(&self, player: &ActiveChain) -> u64 {
+        let response = self.query(&self.host, &format!("query {{ playerBalance(chainId: \"{}\") }}", player.id())).await;
+        response["playerBalance"].as_u64().unwrap()
+    }
+
+    /// The names on one page of the host's player list, `arguments` being the page's.
+    async fn player_page(&self, arguments: &str) -> Vec<String> {
+        let response = self.query(&self.host, &format!("query {{ players{} {{ name }} }}", arguments)).await;
+        response["players"].as_array().unwrap().iter().map(|player| player["name"].as_str().unwrap().to_string()).collect()
+    }
+
+    async fn own_balance(&self, player: &ActiveChain) -> u64 {
+        let response = self.query(player, "query { myPlayer { balance } }").await;
+        response["myPlayer"]["balance"].as_u64().unwrap()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_players_register_through_host() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+    let bob = table.seat_player("Bob").await;
+
+    let response = table.query(&table.host, "query { playerCount }").await;
+    assert_eq!(response["playerCount"], 2);
+    assert_eq!(table.own_balance(&alice).await, INITIAL_BALANCE);
+    assert_eq!(table.own_balance(&bob).await, INITIAL_BALANCE);
+    let response = table.query(&alice, "query { pendingRequests { requestId } }").await;
+    assert_eq!(response["pendingRequests"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_player_pages_stay_in_bounds() {
+    let table = Table::open().await;
+    assert!(table.player_page("").await.is_empty());
+    assert!(table.player_page("(offset: 3)").await.is_empty());
+
+    table.seat_player("Bob").await;
+    table.seat_player("Alice").await;
+    assert_eq!(table.player_page("").await.len(), 2);
+    assert!(table.player_page("(limit: 0)").await.is_empty());
+    assert!(table.player_page("(limit: 0, sortBy: NAME)").await.is_empty());
+    assert!(table.player_page("(offset: 2)").await.is_empty());
+    assert!(table.player_page("(offset: 5, sortBy: NAME)").await.is_empty());
+    assert_eq!(table.player_page("(offset: 1, limit: 5, sortBy: NAME)").await, vec!["Bob"]);
+    assert_eq!(table.player_page("(offset: 1, limit: 1)").await.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bets_settle_across_chains() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+    let bob = table.seat_player("Bob").await;
+
+    table.bet(&alice, BetType::Red, 10).await;
+    table.bet(&bob, BetType::Black, 20).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+    bob.handle_received_messages().await;
+
+    let response = table.query(&table.host, "query { totalPot }").await;
+    assert_eq!(response["totalPot"], 30);
+    assert_eq!(table.own_balance(&alice).await, 90);
+    assert_eq!(table.own_balance(&bob).await, 80);
+
+    table.operate(&table.host, Operation::SpinWheel { table_id: DEFAULT_TABLE_ID }).await;
+    let response = table.query(&table.host, "query { lastResult { number color } totalPot }").await;
+    assert_eq!(response["totalPot"], 0);
+    let number = response["lastResult"]["number"].as_u64().unwrap();
+    let color = response["lastResult"]["color"].as_str().unwrap().to_string();
+    let (alice_expected, bob_expected) = match color.as_str() {
+        "red" => (110, 80),
+        "black" => (90, 120),
+        _ => (90, 80),
+    };
+    assert_eq!(table.host_balance(&alice).await, alice_expected);
+    assert_eq!(table.host_balance(&bob).await, bob_expected);
+
+    // The private payout message and the public event stream both reach the players.
+    alice.handle_received_messages().await;
+    bob.handle_received_messages().await;
+    assert_eq!(table.own_balance(&alice).await, alice_expected);
+    assert_eq!(table.own_balance(&bob).await, bob_expected);
+
+    alice.handle_new_events().await;
+    let response = table.query(&alice, "query { history lastResult { number } }").await;
+    assert_eq!(response["lastResult"]["number"], number);
+    assert_eq!(response["history"].as_array().unwrap().last().unwrap().as_u64(), Some(number));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_only_bettors_reconcile_after_a_round() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+    let bob = table.seat_player("Bob").await;
+    table.bet(&alice, BetType::Red, 10).await;
+    table.host.handle_received_messages().await;
+    table.operate(&table.host, Operation::SpinWheel { table_id: DEFAULT_TABLE_ID }).await;
+
+    // Bob sat the round out, so his chain asks the host for nothing.
+    bob.handle_new_events().await;
+    let response = table.query(&bob, "query { balanceIsStale }").await;
+    assert_eq!(response["balanceIsStale"], false);
+    let height = table.host.get_tip_height().await;
+    table.host.handle_received_messages().await;
+    assert_eq!(table.host.get_tip_height().await, height);
+
+    alice.handle_received_messages().await;
+    alice.handle_new_events().await;
+    let response = table.query(&alice, "query { balanceIsStale }").await;
+    assert_eq!(response["balanceIsStale"], true);
+    table.host.handle_received_messages().await;
+    assert!(table.host.get_tip_height().await > height);
+    alice.handle_received_messages().await;
+    let response = table.query(&alice, "query { balanceIsStale }").await;
+    assert_eq!(response["balanceIsStale"], false);
+    assert_eq!(table.own_balance(&alice).await, table.host_balance(&alice).await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rejected_bet_is_reported_to_player() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+
+    table.bet(&alice, BetType::Red, INITIAL_BALANCE + 1).await;
+    let response = table.query(&alice, "query { pendingRequests { kind amount } }").await;
+    assert_eq!(response["pendingRequests"][0]["kind"], "PLACE_BET");
+
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    let response = table.query(&alice, "query { pendingRequests { requestId } lastErrors { errorMessage } }").await;
+    assert_eq!(response["pendingRequests"].as_array().unwrap().len(), 0);
+    assert_eq!(response["lastErrors"][0]["errorMessage"], "Insufficient balance");
+    assert_eq!(table.host_balance(&alice).await, INITIAL_BALANCE);
+    let response = table.query(&table.host, "query { totalPot }").await;
+    assert_eq!(response["totalPot"], 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_leaving_returns_chips_to_wallet() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+
+    table.bet(&alice, BetType::Odd, 25).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    table.operate(&alice, Operation::LeaveTable).await;
+    let response = table.query(&alice, "query { pendingRequests { kind } }").await;
+    assert_eq!(response["pendingRequests"][0]["kind"], "LEAVE");
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+    let response = table.query(&alice, "query { pendingRequests { kind } }").await;
+    assert_eq!(response["pendingRequests"].as_array().unwrap().len(), 0);
+
+    // The default leave policy refunds the open bet.
+    let response = table.query(&table.host, "query { playerCount totalPot }").await;
+    assert_eq!(response["playerCount"], 0);
+    assert_eq!(response["totalPot"], 0);
+    let response = table.query(&alice, "query { myPlayer { balance } walletBalance }").await;
+    assert_eq!(response["myPlayer"], Value::Null);
+    assert_eq!(response["walletBalance"], INITIAL_BALANCE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_kicked_player_forfeits_open_bets() {
+    let table = Table::open().await;
+    let config = TableConfig { leave_policy: LeavePolicy::Forfeit, ..TableConfig::default() };
+    table.operate(&table.host, Operation::ConfigureTable { table_id: DEFAULT_TABLE_ID, config }).await;
+    let alice = table.seat_player("Alice").await;
+    let bob = table.seat_player("Bob").await;
+    table.bet(&alice, BetType::Red, 10).await;
+    table.bet(&bob, BetType::Black, 20).await;
+    table.host.handle_received_messages().await;
+
+    table.operate(&table.host, Operation::KickPlayer { chain_id: alice.id().to_string() }).await;
+    alice.handle_received_messages().await;
+
+    let query = format!("query {{ playerCount totalPot player(chainId: \"{}\") {{ name }} }}", alice.id());
+    let response = table.query(&table.host, &query).await;
+    assert_eq!(response["playerCount"], 1);
+    assert_eq!(response["totalPot"], 20);
+    assert_eq!(response["player"], Value::Null);
+    let response = table.query(&alice, "query { myPlayer { balance } walletBalance }").await;
+    assert_eq!(response["myPlayer"], Value::Null);
+    assert_eq!(response["walletBalance"], INITIAL_BALANCE - 10);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unseated_player_joins_with_wallet_chips() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+    table.operate(&table.host, Operation::CreateTable { config: Default::default() }).await;
+    table.operate(&alice, Operation::LeaveTable).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    let host_chain_id = table.host.id().to_string();
+    table.operate(&alice, Operation::JoinTable { host_chain_id, table_id: DEFAULT_TABLE_ID + 1, invite_code: None }).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    let response = table.query(&alice, "query { myPlayer { tableId balance } walletBalance pendingRequests { kind } }").await;
+    assert_eq!(response["myPlayer"]["tableId"], DEFAULT_TABLE_ID + 1);
+    assert_eq!(response["myPlayer"]["balance"], INITIAL_BALANCE);
+    assert_eq!(response["walletBalance"], 0);
+    assert_eq!(response["pendingRequests"].as_array().unwrap().len(), 0);
+    assert_eq!(table.host_balance(&alice).await, INITIAL_BALANCE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_autoplay_bets_every_round_until_done() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+
+    let bets = vec![BetLayout { bet_type: BetType::Red, numbers: vec![], amount: 5 }];
+    table.operate(&alice, Operation::StartAutoplay { bets, rounds: 3, stop_loss: None, take_profit: None }).await;
+    for _ in 0..3 {
+        table.host.handle_received_messages().await;
+        let response = table.query(&table.host, "query { totalPot }").await;
+        assert_eq!(response["totalPot"], 5);
+        table.operate(&table.host, Operation::SpinWheel { table_id: DEFAULT_TABLE_ID }).await;
+        // The result arrives after the bet confirmation and places the next round.
+        alice.handle_received_messages().await;
+    }
+
+    let response = table.query(&alice, "query { autoplay { roundsPlayed stopped } }").await;
+    assert_eq!(response["autoplay"]["roundsPlayed"], 3);
+    assert_eq!(response["autoplay"]["stopped"], "ROUNDS_COMPLETED");
+    table.host.handle_received_messages().await;
+    let response = table.query(&table.host, "query { totalPot }").await;
+    assert_eq!(response["totalPot"], 0);
+    assert_eq!(table.own_balance(&alice).await, table.host_balance(&alice).await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_strategy_sizes_each_round_until_stopped() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+
+    let bet = BetLayout { bet_type: BetType::Red, numbers: vec![], amount: 1 };
+    table.operate(&alice, Operation::StartStrategy { kind: StrategyKind::Martingale, bet }).await;
+    let mut stake = 1;
+    for _ in 0..3 {
+        table.host.handle_received_messages().await;
+        let response = table.query(&table.host, "query { totalPot }").await;
+        assert_eq!(response["totalPot"], stake);
+        table.operate(&table.host, Operation::SpinWheel { table_id: DEFAULT_TABLE_ID }).await;
+        let response = table.query(&table.host, "query { lastResult { color } }").await;
+        stake = if response["lastResult"]["color"] == "red" { 1 } else { stake * 2 };
+        alice.handle_received_messages().await;
+    }
+
+    let response = table.query(&alice, "query { strategy { kind roundsPlayed lastBets { amount } } }").await;
+    assert_eq!(response["strategy"]["kind"], "MARTINGALE");
+    assert_eq!(response["strategy"]["roundsPlayed"], 3);
+    assert_eq!(response["strategy"]["lastBets"][0]["amount"], stake);
+
+    table.operate(&alice, Operation::StopStrategy).await;
+    let response = table.query(&alice, "query { strategy { stopped } }").await;
+    assert_eq!(response["strategy"]["stopped"], "STOPPED");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_host_enforces_player_limits() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+
+    let limits = PlayerLimits { daily_loss: Some(30), ..PlayerLimits::default() };
+    table.operate(&alice, Operation::ChangeLimits { change: LimitChange::SetLimits(limits) }).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+    let response = table.query(&alice, "query { responsibleGaming { limits { dailyLoss } } }").await;
+    assert_eq!(response["responsibleGaming"]["limits"]["dailyLoss"], 30);
+
+    table.bet(&alice, BetType::Red, 20).await;
+    table.bet(&alice, BetType::Black, 20).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+    let response = table.query(&alice, "query { lastErrors { errorMessage } }").await;
+    assert_eq!(response["lastErrors"][0]["errorMessage"], "Bet would exceed daily loss limit of 30");
+    let response = table.query(&table.host, "query { totalPot }").await;
+    assert_eq!(response["totalPot"], 20);
+
+    // Loosening waits a day; the tighter limit stays in force meanwhile.
+    let limits = PlayerLimits { daily_loss: Some(1_000), ..PlayerLimits::default() };
+    table.operate(&alice, Operation::ChangeLimits { change: LimitChange::SetLimits(limits) }).await;
+    table.host.handle_received_messages().await;
+    let query = format!("query {{ responsibleGaming(chainId: \"{}\") {{ limits {{ dailyLoss }} pending {{ limits {{ dailyLoss }} }} }} }}", alice.id());
+    let response = table.query(&table.host, &query).await;
+    assert_eq!(response["responsibleGaming"]["limits"]["dailyLoss"], 30);
+    assert_eq!(response["responsibleGaming"]["pending"]["limits"]["dailyLoss"], 1_000);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bounced_bet_confirm_refunds_the_bet() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+
+    table.reject_reply(&alice, Table::bet_operation(&alice, BetType::Red, 10)).await;
+
+    let response = table.query(&table.host, "query { totalPot }").await;
+    assert_eq!(response["totalPot"], 0);
+    assert_eq!(table.host_balance(&alice).await, INITIAL_BALANCE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bounced_registration_releases_the_seat() {
+    let table = Table::open().await;
+    let alice = table.validator.new_chain().await;
+    let host_chain_id = table.host.id().to_string();
+    table.operate(&alice, Operation::JoinTable { host_chain_id, table_id: DEFAULT_TABLE_ID, invite_code: None }).await;
+
+    table.reject_reply(&alice, Operation::RegisterPlayer {
+        table_id: DEFAULT_TABLE_ID,
+        player_id: alice.id().to_string(),
+        name: "Alice".to_string(),
+        initial_balance: INITIAL_BALANCE,
+        invite_code: None,
+    }).await;
+
+    let response = table.query(&table.host, "query { playerCount }").await;
+    assert_eq!(response["playerCount"], 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bounced_payout_is_resent_as_a_snapshot() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+    table.bet(&alice, BetType::Red, 10).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    table.reject_from_host(&alice, Operation::SpinWheel { table_id: DEFAULT_TABLE_ID }).await;
+    assert_eq!(table.own_balance(&alice).await, 90);
+
+    // The next host block sends the balance the host holds now, not the old payout.
+    table.operate(&table.host, Operation::Tick).await;
+    alice.handle_received_messages().await;
+    assert_eq!(table.own_balance(&alice).await, table.host_balance(&alice).await);
+    let response = table.query(&alice, "query { balanceIsStale }").await;
+    assert_eq!(response["balanceIsStale"], false);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bounced_leave_confirm_is_dropped_once_reseated() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+
+    table.reject_from_host(&alice, Operation::KickPlayer { chain_id: alice.id().to_string() }).await;
+    table.operate(&alice, Operation::RegisterPlayer {
+        table_id: DEFAULT_TABLE_ID,
+        player_id: alice.id().to_string(),
+        name: "Alice".to_string(),
+        initial_balance: INITIAL_BALANCE,
+        invite_code: None,
+    }).await;
+    table.host.handle_received_messages().await;
+
+    table.operate(&table.host, Operation::Tick).await;
+    alice.handle_received_messages().await;
+    let response = table.query(&table.host, "query { playerCount }").await;
+    assert_eq!(response["playerCount"], 1);
+    let response = table.query(&alice, "query { myPlayer { chainId } }").await;
+    assert_eq!(response["myPlayer"]["chainId"], alice.id().to_string());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_empty_snapshot_unseats_the_player() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+    let bets = vec![BetLayout { bet_type: BetType::Red, numbers: vec![], amount: 5 }];
+    table.operate(&alice, Operation::StartAutoplay { bets, rounds: 3, stop_loss: None, take_profit: None }).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    // The kick's leave confirm never lands, so only the snapshot tells Alice she lost her seat.
+    table.reject_from_host(&alice, Operation::KickPlayer { chain_id: alice.id().to_string() }).await;
+    table.operate(&alice, Operation::SyncBalance).await;
+    table.host.handle_received_messages().await;
+    alice.handle_received_messages().await;
+
+    let response = table.query(&alice, "query { myPlayer { balance } autoplay { stopped } }").await;
+    assert_eq!(response["myPlayer"], Value::Null);
+    assert_eq!(response["autoplay"]["stopped"], "UNSEATED");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_spectator_mirrors_the_host_table() {
+    let table = Table::open().await;
+    let alice = table.seat_player("Alice").await;
+    let spectator = table.validator.new_chain().await;
+    table.operate(&spectator, Operation::Spectate { host_chain_id: table.host.id().to_string() }).await;
+
+    table.bet(&alice, BetType::Red, 10).await;
+    table.host.handle_received_messages().await;
+    spectator.handle_new_events().await;
+    let response = table.query(&spectator, "query { amISpectator myPlayer { name } tables { tableId } totalPot }").await;
+    assert_eq!(response["amISpectator"], true);
+    assert_eq!(response["myPlayer"], Value::Null);
+    assert_eq!(response["tables"][0]["tableId"], DEFAULT_TABLE_ID);
+    assert_eq!(response["totalPot"], 10);
+
+    table.operate(&table.host, Operation::SpinWheel { table_id: DEFAULT_TABLE_ID }).await;
+    spectator.handle_new_events().await;
+    let query = "query { totalPot history lastResult { number color } }";
+    let response = table.query(&spectator, query).await;
+    assert_eq!(response, table.query(&table.host, query).await);
+    assert_eq!(response["totalPot"], 0);
+    assert_eq!(response["history"].as_array().unwrap().len(), 1);
+
+    // Watching never registers a player at the host.
+    let response = table.query(&table.host, "query { playerCount }").await;
+    assert_eq!(response["playerCount"], 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_spectator_without_host_opens_no_table() {
+    let argument = InstantiationArgument { spectator: true, ..InstantiationArgument::default() };
+    let (_validator, app_id, chain) = TestValidator::with_current_application::<RouletteAbi, (), InstantiationArgument>((), argument).await;
+
+    let response = chain.graphql_query(app_id, "query { amISpectator amIHost tables { tableId } }").await.response;
+    assert_eq!(response["amISpectator"], true);
+    assert_eq!(response["amIHost"], false);
+    assert_eq!(response["tables"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_solo_bet_settles_in_the_same_block() {
+    let argument = InstantiationArgument { mode: TableMode::Solo, ..InstantiationArgument::default() };
+    let (_validator, app_id, chain) = TestValidator::with_current_application::<RouletteAbi, (), InstantiationArgument>((), argument).await;
+    let operate = |operation| chain.add_block(move |block| { block.with_operation(app_id, operation); });
+    operate(Operation::RegisterPlayer {
+        table_id: DEFAULT_TABLE_ID,
+        player_id: chain.id().to_string(),
+        name: "Alice".to_string(),
+        initial_balance: INITIAL_BALANCE,
+        invite_code: None,
+    }).await;
+    operate(Table::bet_operation(&chain, BetType::Red, 10)).await;
+
+    let query = format!("query {{ totalPot history lastResult {{ color }} myPlayer {{ balance }} playerBalance(chainId: \"{}\") }}", chain.id());
+    let response = chain.graphql_query(app_id, &query).await.response;
+    assert_eq!(response["totalPot"], 0);
+    assert_eq!(response["history"].as_array().unwrap().len(), 1);
+    let expected = if response["lastResult"]["color"] == "red" { 110 } else { 90 };
+    assert_eq!(response["playerBalance"], expected);
+    assert_eq!(response["myPlayer"]["balance"], expected);
+
+    // Nothing was sent, so there is nothing to receive.
+    let height = chain.get_tip_height().await;
+    chain.handle_received_messages().await;
+    assert_eq!(chain.get_tip_height().await, height);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rooms_are_leased_to_the_requesting_chain() {
+    let table = Table::open().await;
+    let registry = table.validator.new_chain().await;
+    table.operate(&registry, Operation::EnableRegistry).await;
+    let room = table.validator.new_chain().await.id().to_string();
+    table.operate(&registry, Operation::AddRoom { chain_id: room.clone(), name: "Room 1".to_string() }).await;
+
+    let alice = table.validator.new_chain().await;
+    let bob = table.validator.new_chain().await;
+    let registry_chain_id = registry.id().to_string();
+    let claim = || Operation::ClaimRoom { registry_chain_id: registry_chain_id.clone(), chain_id: room.clone() };
+    let release = || Operation::ReleaseRoom { registry_chain_id: registry_chain_id.clone(), chain_id: room.clone() };
+    // Claims from different chains can land in the same block in either order, so settle alice's first.
+    table.operate(&alice, claim()).await;
+    registry.handle_received_messages().await;
+    table.operate(&bob, claim()).await;
+    registry.handle_received_messages().await;
+
+    let query = format!("query {{ room(chainId: \"{}\") {{ claimedBy }} availableRooms {{ chainId }} }}", room);
+    let response = table.query(&registry, &query).await;
+    assert_eq!(response["room"]["claimedBy"], alice.id().to_string());
+    assert_eq!(response["availableRooms"].as_array().unwrap().len(), 0);
+
+    // Only the chain holding the lease can give it up.
+    table.operate(&bob, release()).await;
+    registry.handle_received_messages().await;
+    let response = table.query(&registry, &query).await;
+    assert_eq!(response["room"]["claimedBy"], alice.id().to_string());
+
+    table.operate(&alice, release()).await;
+    registry.handle_received_messages().await;
+    let response = table.query(&registry, &query).await;
+    assert_eq!(response["room"]["claimedBy"], Value::Null);
+    assert_eq!(response["availableRooms"][0]["chainId"], room);
+}
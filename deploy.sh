@@ -216,11 +216,43 @@ done
 
 echo -e "\n${GREEN}✓ Chain pool created (${#CHAIN_POOL[@]} chains)${NC}\n"
 
+# Registry chain: runs the app in registry mode and leases the pool chains to hosts
+REGISTRY_CHAIN=$(linera open-chain --from "$DEFAULT_CHAIN" 2>&1 | grep -oE '[a-f0-9]{64}' | tail -1)
+if [ -z "$REGISTRY_CHAIN" ]; then
+    echo -e "${RED}Error: Could not create the registry chain${NC}"
+    exit 1
+fi
+echo -e "${GREEN}✓ Registry chain: $REGISTRY_CHAIN${NC}\n"
+
 # Sync chains
 echo -e "${YELLOW}Synchronizing chains...${NC}"
 linera sync > /dev/null 2>&1 || true
 echo -e "${GREEN}✓ Chains synchronized${NC}\n"
 
+# ============================================================
+# SECTION 6.5: Room Registry Setup
+# ============================================================
+echo -e "${YELLOW}Enabling the room registry...${NC}"
+
+# The service owns the wallet while it runs, so start it just long enough to submit the setup blocks
+linera service --port "$SERVICE_PORT" > "$DATA_DIR/registry-setup.log" 2>&1 &
+SETUP_SERVICE_PID=$!
+sleep 5
+
+REGISTRY_URL="http://localhost:$SERVICE_PORT/chains/$REGISTRY_CHAIN/applications/$APP_ID"
+registry_mutation() {
+    curl -s -X POST "$REGISTRY_URL" -H 'Content-Type: application/json' \
+        -d "{\"query\":\"$1\"}" > /dev/null
+}
+registry_mutation 'mutation { enableRegistry { accepted } }'
+for i in "${!CHAIN_POOL[@]}"; do
+    registry_mutation "mutation { addRoom(chainId: \\\"${CHAIN_POOL[$i]}\\\", name: \\\"Room $((i+1))\\\") { accepted } }"
+done
+
+kill $SETUP_SERVICE_PID 2>/dev/null || true
+wait $SETUP_SERVICE_PID 2>/dev/null || true
+echo -e "${GREEN}✓ Registry enabled with ${#CHAIN_POOL[@]} rooms${NC}\n"
+
 # ============================================================
 # SECTION 7: Frontend Configuration
 # ============================================================
@@ -238,18 +270,10 @@ VITE_FAUCET_URL=$TESTNET_FAUCET
 VITE_APP_ID=$APP_ID
 VITE_DEFAULT_CHAIN=$DEFAULT_CHAIN
 VITE_HOST_CHAIN=$DEFAULT_CHAIN
+# Chain running the app in registry mode; rooms are claimed through it.
+VITE_REGISTRY_CHAIN=$REGISTRY_CHAIN
 EOF
 
-# Generate chainPool.json
-echo '{"chains":[' > public/chainPool.json
-for i in "${!CHAIN_POOL[@]}"; do
-    COMMA=""
-    [ $i -gt 0 ] && COMMA=","
-    CHAIN_ID="${CHAIN_POOL[$i]}"
-    echo "${COMMA}{\"id\":$((i+1)),\"chainId\":\"$CHAIN_ID\",\"inUse\":false}" >> public/chainPool.json
-done
-echo ']}' >> public/chainPool.json
-
 # Generate rooms.json (use first 3 chains)
 cat > public/rooms.json << EOF
 {
@@ -276,6 +300,7 @@ echo -e "\n${BLUE}Deployment Summary:${NC}"
 echo -e "  APP_ID:        $APP_ID"
 echo -e "  DEFAULT_CHAIN: $DEFAULT_CHAIN"
 echo -e "  CHAINS:        ${#CHAIN_POOL[@]} total"
+echo -e "  REGISTRY:      $REGISTRY_CHAIN"
 echo -e "  STORAGE:       $DATA_DIR"
 
 echo -e "\n${BLUE}Next Steps:${NC}"
@@ -294,7 +319,6 @@ echo -e "   http://localhost:5173"
 
 echo -e "\n${BLUE}Configuration files created:${NC}"
 echo -e "  - frontend/.env"
-echo -e "  - frontend/public/chainPool.json"
 echo -e "  - frontend/public/rooms.json"
 echo -e "  - $DATA_DIR/app_id.txt"
 
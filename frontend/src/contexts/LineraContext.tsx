@@ -1,11 +1,16 @@
 import { createContext, useContext, useState, useCallback, useEffect, useMemo, ReactNode } from 'react';
 import type { Room, RoomsConfig } from '../types/rooms';
-import type { PooledChain, ChainPool } from '../types/chainPool';
+import type { PooledChain, RegistryRoom } from '../types/chainPool';
 
 const LOCAL_NODE = import.meta.env.VITE_NODE_URL || 'http://localhost:8080';
 const LOCAL_FAUCET = import.meta.env.VITE_FAUCET_URL || 'http://localhost:8079';
 const DEFAULT_CHAIN = import.meta.env.VITE_DEFAULT_CHAIN || '';
 const HOST_CHAIN = import.meta.env.VITE_HOST_CHAIN || DEFAULT_CHAIN;
+const REGISTRY_CHAIN = import.meta.env.VITE_REGISTRY_CHAIN || '';
+const ROOM_POLL_MS = 15000;
+const ROOM_HEARTBEAT_MS = 30000;
+const CLAIM_CHECK_MS = 1000;
+const CLAIM_CHECKS = 10;
 
 type GameMode = 'selecting' | 'solo' | 'host' | 'join';
 
@@ -93,18 +98,53 @@ export function LineraProvider({ children }: { children: ReactNode }) {
     loadRooms();
   }, []);
 
+  const queryRegistry = useCallback(async (graphqlQuery: string, variables?: Record<string, unknown>) => {
+    if (!REGISTRY_CHAIN || !appId) throw new Error('No room registry configured');
+    const res = await fetch(`${LOCAL_NODE}/chains/${REGISTRY_CHAIN}/applications/${appId}`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ query: graphqlQuery, variables }),
+    });
+    if (!res.ok) throw new Error(`Request failed: ${res.statusText}`);
+    const result = await res.json();
+    if (result.errors) throw new Error(result.errors[0]?.message || 'GraphQL error');
+    return result.data;
+  }, [appId]);
+
+  const loadPool = useCallback(async () => {
+    try {
+      const data = await queryRegistry(`query { rooms { chainId name claimedBy } availableRooms { chainId } }`);
+      const available = new Set((data.availableRooms as { chainId: string }[]).map(r => r.chainId));
+      const registryRooms: RegistryRoom[] = data.rooms;
+      setChainPool(registryRooms.map((r, i) => ({ id: i + 1, chainId: r.chainId, inUse: !available.has(r.chainId) })));
+      const hosted = registryRooms.filter(r => !available.has(r.chainId));
+      if (hosted.length > 0) setRooms(hosted.map((r, i) => ({ id: i + 1, name: r.name, chainId: r.chainId })));
+    } catch {}
+    finally { setPoolLoading(false); }
+  }, [queryRegistry]);
+
   useEffect(() => {
-    const loadPool = async () => {
-      try {
-        const res = await fetch('/chainPool.json');
-        if (!res.ok) { setPoolLoading(false); return; }
-        const data: ChainPool = await res.json();
-        setChainPool(data.chains.filter(c => c.chainId?.length === 64));
-      } catch {}
-      finally { setPoolLoading(false); }
-    };
+    if (!REGISTRY_CHAIN) { setPoolLoading(false); return; }
     loadPool();
-  }, []);
+    const interval = setInterval(loadPool, ROOM_POLL_MS);
+    return () => clearInterval(interval);
+  }, [loadPool]);
+
+  // Lease changes are operations on our own chain; the registry takes our chain as the claimant.
+  const requestRoom = useCallback((action: 'claimRoom' | 'releaseRoom' | 'heartbeatRoom', roomChainId: string) => {
+    return query(
+      `mutation($registryChainId: String!, $chainId: String!) { ${action}(registryChainId: $registryChainId, chainId: $chainId) { accepted } }`,
+      { registryChainId: REGISTRY_CHAIN, chainId: roomChainId },
+    );
+  }, [query]);
+
+  useEffect(() => {
+    if (!hostedChainId) return;
+    const interval = setInterval(() => {
+      requestRoom('heartbeatRoom', hostedChainId).catch(() => {});
+    }, ROOM_HEARTBEAT_MS);
+    return () => clearInterval(interval);
+  }, [hostedChainId, requestRoom]);
 
   const queryHost = useCallback(async (graphqlQuery: string, variables?: Record<string, unknown>) => {
     if (!appId) throw new Error('Not connected');
@@ -190,14 +230,34 @@ export function LineraProvider({ children }: { children: ReactNode }) {
     setError(null);
   }, []);
 
+  /** Waits for the registry to handle our claim; true once the room's committed lease is ours. */
+  const awaitClaim = useCallback(async (roomChainId: string): Promise<boolean> => {
+    for (let check = 0; check < CLAIM_CHECKS; check++) {
+      await new Promise(resolve => setTimeout(resolve, CLAIM_CHECK_MS));
+      const data = await queryRegistry(
+        `query($chainId: String!) { room(chainId: $chainId) { claimedBy } }`,
+        { chainId: roomChainId },
+      ).catch(() => null);
+      const claimedBy = data?.room?.claimedBy;
+      if (claimedBy === chainId) return true;
+      if (claimedBy) return false;
+    }
+    return false;
+  }, [chainId, queryRegistry]);
+
   const hostGame = useCallback(async (): Promise<string | null> => {
-    const available = chainPool.find(c => !c.inUse);
-    if (!available) return null;
-    setChainPool(prev => prev.map(c => c.id === available.id ? { ...c, inUse: true } : c));
-    setHostedChainId(available.chainId);
-    setGameMode('host');
-    return available.chainId;
-  }, [chainPool]);
+    // The registry arbitrates claims, so two hosts can never end up on the same chain.
+    for (const candidate of chainPool.filter(c => !c.inUse)) {
+      const data = await requestRoom('claimRoom', candidate.chainId).catch(() => null);
+      if (!data?.claimRoom?.accepted) continue;
+      if (!(await awaitClaim(candidate.chainId))) continue;
+      setChainPool(prev => prev.map(c => c.id === candidate.id ? { ...c, inUse: true } : c));
+      setHostedChainId(candidate.chainId);
+      setGameMode('host');
+      return candidate.chainId;
+    }
+    return null;
+  }, [chainPool, requestRoom, awaitClaim]);
 
   const joinGame = useCallback((chainId: string): boolean => {
     if (!chainId || chainId.length !== 64 || !/^[a-f0-9]+$/.test(chainId)) return false;
@@ -208,15 +268,18 @@ export function LineraProvider({ children }: { children: ReactNode }) {
   }, []);
 
   const leaveGame = useCallback(() => {
-    if (hostedChainId) setChainPool(prev => prev.map(c => c.chainId === hostedChainId ? { ...c, inUse: false } : c));
+    if (hostedChainId) {
+      requestRoom('releaseRoom', hostedChainId).catch(() => {});
+      setChainPool(prev => prev.map(c => c.chainId === hostedChainId ? { ...c, inUse: false } : c));
+    }
     setHostedChainId(null);
     setJoinedChainId(null);
     setJoinHostChainId('');
     setGameMode('selecting');
-  }, [hostedChainId]);
+  }, [hostedChainId, requestRoom]);
 
   const subscribe = useCallback((callback: (data: unknown) => void) => {
-    // WebSocket not supported by Linera service - using polling instead
+    // The application service has no subscriptions; useGame polls instead.
     return () => {};
   }, []);
 
@@ -313,11 +313,15 @@ export function useGame() {
       setError(null);
 
       try {
-        await mutate(`
+        const data = await mutate(`
           mutation RegisterPlayer($playerId: String!, $name: String!, $initialBalance: Int!) {
-            registerPlayer(playerId: $playerId, name: $name, initialBalance: $initialBalance)
+            registerPlayer(playerId: $playerId, name: $name, initialBalance: $initialBalance) {
+              accepted
+              errorMessage
+            }
           }
         `, { playerId, name, initialBalance });
+        if (!data.registerPlayer.accepted) throw new Error(data.registerPlayer.errorMessage);
 
         await fetchGameState();
       } catch (err) {
@@ -336,11 +340,15 @@ export function useGame() {
       setError(null);
 
       try {
-        await mutate(`
+        const data = await mutate(`
           mutation PlaceBet($playerId: String!, $betType: BetType!, $numbers: [Int!]!, $amount: Int!) {
-            placeBet(playerId: $playerId, betType: $betType, numbers: $numbers, amount: $amount)
+            placeBet(playerId: $playerId, betType: $betType, numbers: $numbers, amount: $amount) {
+              accepted
+              errorMessage
+            }
           }
         `, { playerId, betType, numbers, amount });
+        if (!data.placeBet.accepted) throw new Error(data.placeBet.errorMessage);
 
         await fetchGameState();
       } catch (err) {
@@ -369,7 +377,7 @@ export function useGame() {
 
       await mutate(`
         mutation {
-          spinWheel
+          spinWheel { accepted }
         }
       `);
 
@@ -435,7 +443,7 @@ export function useGame() {
     if (!isHost) return;
 
     try {
-      await mutate(`mutation { startRound }`);
+      await mutate(`mutation { startRound { accepted } }`);
     } catch (err) {
       console.error('[startRound] Failed to call startRound mutation:', err);
     }
@@ -4,8 +4,11 @@ export interface PooledChain {
   inUse: boolean;
 }
 
-export interface ChainPool {
-  chains: PooledChain[];
+/** A host chain as listed by the on-chain room registry. */
+export interface RegistryRoom {
+  chainId: string;
+  name: string;
+  claimedBy: string | null;
 }
 
 export interface HostedGame {
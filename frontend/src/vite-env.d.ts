@@ -6,6 +6,7 @@ interface ImportMetaEnv {
   readonly VITE_FAUCET_URL?: string
   readonly VITE_DEFAULT_CHAIN?: string
   readonly VITE_HOST_CHAIN?: string
+  readonly VITE_REGISTRY_CHAIN?: string
 }
 
 interface ImportMeta {
@@ -142,6 +142,15 @@ for i in $(seq 2 $CHAIN_POOL_SIZE); do
     sleep 0.5
 done
 
+# Registry chain: runs the app in registry mode and leases the pool chains to hosts
+REGISTRY_CHAIN=$(linera open-chain 2>&1 | grep -oE '[a-f0-9]{64}' | tail -1)
+if [ -z "$REGISTRY_CHAIN" ]; then
+    echo -e "${RED}ERROR: Failed to create the registry chain${NC}"
+    kill $LINERA_PID 2>/dev/null || true
+    exit 1
+fi
+echo -e "${GREEN}   ✓ Registry chain: ${REGISTRY_CHAIN:0:16}...${NC}"
+
 # Sync wallet after batch creation
 linera sync > /dev/null 2>&1 || true
 
@@ -155,6 +164,18 @@ linera service --port 8080 > /tmp/linera-service.log 2>&1 &
 SERVICE_PID=$!
 sleep 3
 curl -s http://localhost:8080 > /dev/null && echo -e "${GREEN}✅ GraphQL service started on port 8080${NC}" || echo -e "${YELLOW}⚠️ GraphQL service may still be starting...${NC}"
+
+# Turn on registry mode and register every pool chain as a room
+REGISTRY_URL="http://localhost:8080/chains/$REGISTRY_CHAIN/applications/$APP_ID"
+registry_mutation() {
+    curl -s -X POST "$REGISTRY_URL" -H 'Content-Type: application/json' \
+        -d "{\"query\":\"$1\"}" > /dev/null
+}
+registry_mutation 'mutation { enableRegistry { accepted } }'
+for i in "${!CHAIN_POOL[@]}"; do
+    registry_mutation "mutation { addRoom(chainId: \\\"${CHAIN_POOL[$i]}\\\", name: \\\"Room $((i+1))\\\") { accepted } }"
+done
+echo -e "${GREEN}✅ Registry enabled with $POOL_COUNT rooms${NC}"
 echo ""
 
 # Step 5: Faucet service (optional - for multiplayer new chain creation)
@@ -169,35 +190,18 @@ echo ""
 echo -e "${YELLOW}🎨 Step 6/7: Setting up frontend...${NC}"
 cd frontend
 
-# Create .env.local file with APP_ID, DEFAULT_CHAIN, and HOST_CHAIN
+# Create .env.local file with APP_ID, DEFAULT_CHAIN, HOST_CHAIN and REGISTRY_CHAIN
 cat > .env.local << EOF
 VITE_NODE_URL=http://localhost:8080
 VITE_FAUCET_URL=http://localhost:8079
 VITE_APP_ID=$APP_ID
 VITE_DEFAULT_CHAIN=$DEFAULT_CHAIN
 VITE_HOST_CHAIN=$DEFAULT_CHAIN
+VITE_REGISTRY_CHAIN=$REGISTRY_CHAIN
 EOF
 
 echo -e "${GREEN}✅ Environment configured${NC}"
 
-# Generate chainPool.json for frontend
-echo -e "${BLUE}   Generating chainPool.json...${NC}"
-
-# Start JSON array
-echo '{"chains":[' > public/chainPool.json
-
-# Add each chain
-for i in "${!CHAIN_POOL[@]}"; do
-    COMMA=""
-    [ $i -gt 0 ] && COMMA=","
-    echo "${COMMA}{\"id\":$((i+1)),\"chainId\":\"${CHAIN_POOL[$i]}\",\"inUse\":false}" >> public/chainPool.json
-done
-
-# Close JSON
-echo ']}' >> public/chainPool.json
-
-echo -e "${GREEN}   ✓ chainPool.json created with $POOL_COUNT chains${NC}"
-
 # Install dependencies if needed
 if [ ! -d "node_modules" ]; then
     echo "Installing frontend dependencies..."